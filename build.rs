@@ -0,0 +1,19 @@
+fn main() {
+    #[cfg(feature = "header")]
+    header();
+}
+
+// include/check.h is committed, so C users needn't build with the feature.
+#[cfg(feature = "header")]
+fn header() {
+    println!("cargo::rerun-if-changed=src/ffi.rs");
+    println!("cargo::rerun-if-changed=cbindgen.toml");
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{dir}/cbindgen.toml")).unwrap();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{dir}/src/ffi.rs"))
+        .generate()
+        .expect("Couldn't generate the C header")
+        .write_to_file(format!("{dir}/include/check.h"));
+}
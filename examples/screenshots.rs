@@ -0,0 +1,194 @@
+// Renders the reference crops in src/screenshot/ and the synthetic corpus
+// in tests/screenshots/ that src/screenshot.rs is tested against:
+//
+//   cargo run --example screenshots --features screenshot [-- <font.ttf>]
+//
+// Each corpus file is named for the hand on it. They're drawn, not
+// captured: dark felt, a score bar and a button for the card finder to
+// ignore, cards at a few sizes and positions, and some saved as JPEG at
+// middling quality.
+
+use check::Deck;
+
+use std::path::Path;
+
+use ab_glyph::Font;
+use ab_glyph::FontVec;
+use ab_glyph::Glyph;
+use ab_glyph::PxScale;
+use ab_glyph::ScaleFont;
+use ab_glyph::point;
+use image::GrayImage;
+use image::Rgb;
+use image::RgbImage;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops;
+use image::imageops::FilterType;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+const FONT: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf";
+
+const RANKS: [&str; 13] = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K"];
+const SUITS: [(&str, char); 4] = [("C", '♣'), ("D", '♦'), ("H", '♥'), ("S", '♠')];
+
+const BLACK: Rgb<u8> = Rgb([20, 20, 20]);
+const RED: Rgb<u8> = Rgb([200, 30, 30]);
+const WHITE: Rgb<u8> = Rgb([248, 248, 244]);
+
+// Lays `text` out on one line at `scale`, as glyphs with their offsets.
+fn layout(font: &FontVec, text: &str, scale: f32) -> Vec<Glyph> {
+    let scaled = font.as_scaled(PxScale::from(scale));
+    let mut x = 0.0;
+    text.chars()
+        .map(|c| {
+            let glyph = scaled.scaled_glyph(c);
+            let advance = scaled.h_advance(glyph.id);
+            let glyph = Glyph {
+                position: point(x, scaled.ascent()),
+                ..glyph
+            };
+            x += advance;
+            glyph
+        })
+        .collect()
+}
+
+// Draws `text` so its print is `height` pixels tall with its top-left at
+// (x, y), and returns how wide it came out.
+fn draw_text(image: &mut RgbImage, font: &FontVec, text: &str, height: f32, (x, y): (f32, f32), colour: Rgb<u8>) -> f32 {
+    let bounds = |glyphs: &[Glyph]| {
+        glyphs
+            .iter()
+            .filter_map(|glyph| font.outline_glyph(glyph.clone()))
+            .map(|outline| outline.px_bounds())
+            .reduce(|a, b| ab_glyph::Rect {
+                min: point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+                max: point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+            })
+            .unwrap()
+    };
+    let first = bounds(&layout(font, text, 100.0));
+    let glyphs = layout(font, text, 100.0 * height / first.height());
+    let drawn = bounds(&glyphs);
+
+    for outline in glyphs.into_iter().filter_map(|glyph| font.outline_glyph(glyph)) {
+        let at = outline.px_bounds().min;
+        outline.draw(|gx, gy, coverage| {
+            let px = (x + at.x - drawn.min.x) as i64 + gx as i64;
+            let py = (y + at.y - drawn.min.y) as i64 + gy as i64;
+            if px < 0 || py < 0 || px >= image.width() as i64 || py >= image.height() as i64 {
+                return;
+            }
+            let pixel = image.get_pixel_mut(px as u32, py as u32);
+            for (channel, ink) in pixel.0.iter_mut().zip(colour.0) {
+                *channel = (*channel as f32 * (1.0 - coverage) + ink as f32 * coverage) as u8;
+            }
+        });
+    }
+    drawn.width()
+}
+
+fn fill(image: &mut RgbImage, (x, y, width, height): (u32, u32, u32, u32), colour: Rgb<u8>) {
+    for py in y..y + height {
+        for px in x..x + width {
+            image.put_pixel(px, py, colour);
+        }
+    }
+}
+
+// One glyph, cropped to its print and scaled like the module's templates.
+fn crop(font: &FontVec, text: &str, (width, height): (u32, u32)) -> GrayImage {
+    let mut canvas = RgbImage::from_pixel(400, 200, Rgb([255, 255, 255]));
+    let drawn = draw_text(&mut canvas, font, text, 120.0, (10.0, 10.0), Rgb([0, 0, 0]));
+    let gray = imageops::grayscale(&canvas);
+    let glyph = imageops::crop_imm(&gray, 10, 10, drawn.ceil() as u32, 120).to_image();
+    imageops::resize(&glyph, width, height, FilterType::Triangle)
+}
+
+fn card(image: &mut RgbImage, font: &FontVec, (x, y): (u32, u32), width: u32, rank: &str, suit: char, red: bool) {
+    let height = width * 7 / 5;
+    let colour = if red { RED } else { BLACK };
+    fill(image, (x, y, width, height), Rgb([170, 170, 170]));
+    fill(image, (x + 2, y + 2, width - 4, height - 4), WHITE);
+
+    let (w, h) = (width as f32, height as f32);
+    draw_text(image, font, rank, h * 0.12, (x as f32 + w * 0.07, y as f32 + h * 0.05), colour);
+    draw_text(image, font, &suit.to_string(), h * 0.09, (x as f32 + w * 0.07, y as f32 + h * 0.20), colour);
+    draw_text(image, font, &suit.to_string(), h * 0.25, (x as f32 + w * 0.36, y as f32 + h * 0.40), colour);
+}
+
+fn screenshot(font: &FontVec, rng: &mut StdRng, (width, height): (u32, u32)) -> (RgbImage, String) {
+    let mut image = RgbImage::from_fn(width, height, |_, y| {
+        let shade = y as f32 / height as f32;
+        Rgb([(20.0 - 10.0 * shade) as u8, (90.0 - 40.0 * shade) as u8, (50.0 - 20.0 * shade) as u8])
+    });
+
+    // A score bar and a button, both white, neither card-shaped.
+    let unit = height as f32 / 100.0;
+    fill(&mut image, (0, 0, width, (unit * 8.0) as u32), Rgb([30, 30, 60]));
+    draw_text(&mut image, font, "CHIPS 1200  BET 10", unit * 4.0, (unit * 2.0, unit * 2.0), WHITE);
+    let button = ((width as f32 * 0.75) as u32, (unit * 88.0) as u32, (unit * 24.0) as u32, (unit * 8.0) as u32);
+    fill(&mut image, button, Rgb([235, 235, 235]));
+    draw_text(&mut image, font, "DEAL", unit * 4.0, (button.0 as f32 + unit * 4.0, button.1 as f32 + unit * 2.0), BLACK);
+
+    let full_hand = Deck::default().deal(rng);
+    let card_width = (unit * rng.random_range(14.0..20.0)) as u32;
+    let gap = (unit * rng.random_range(1.0..4.0)) as u32;
+    let left = (width - 5 * card_width - 4 * gap) / 2;
+    let top = (unit * rng.random_range(35.0..45.0)) as u32;
+    let mut name = Vec::new();
+    for (i, dealt) in full_hand.cards().iter().enumerate() {
+        let text = dealt.to_string();
+        let (rank, suit) = text.split_at(text.len() - 1);
+        let (_, symbol) = SUITS.iter().find(|(name, _)| *name == suit).unwrap();
+        // Held cards sit a little higher.
+        let raise = if rng.random_bool(0.3) { (unit * 4.0) as u32 } else { 0 };
+        let x = left + i as u32 * (card_width + gap);
+        card(&mut image, font, (x, top - raise), card_width, rank, *symbol, suit == "H" || suit == "D");
+        name.push(text);
+    }
+    (image, name.join("-"))
+}
+
+fn main() {
+    let font = std::env::args().nth(1).unwrap_or(FONT.to_string());
+    let font = FontVec::try_from_vec(std::fs::read(&font).expect("Can't read the font")).expect("Not a font");
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    for rank in RANKS {
+        let path = root.join(format!("src/screenshot/ranks/{rank}.png"));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        crop(&font, rank, (24, 32)).save(path).unwrap();
+    }
+    for (name, symbol) in SUITS {
+        let path = root.join(format!("src/screenshot/suits/{name}.png"));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        crop(&font, &symbol.to_string(), (24, 24)).save(path).unwrap();
+    }
+
+    let corpus = root.join("tests/screenshots");
+    std::fs::create_dir_all(&corpus).unwrap();
+    let mut rng = StdRng::seed_from_u64(48);
+    let shots = [
+        ((960, 540), None),
+        ((1280, 720), None),
+        ((800, 450), None),
+        ((1024, 576), None),
+        ((1280, 720), Some(85)),
+        ((1920, 1080), Some(70)),
+        ((960, 540), Some(60)),
+        ((1600, 900), Some(80)),
+    ];
+    for (size, quality) in shots {
+        let (image, name) = screenshot(&font, &mut rng, size);
+        match quality {
+            None => image.save(corpus.join(format!("{name}.png"))).unwrap(),
+            Some(quality) => {
+                let file = std::fs::File::create(corpus.join(format!("{name}.jpg"))).unwrap();
+                image.write_with_encoder(JpegEncoder::new_with_quality(file, quality)).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+/* The C API to check's solver. Generated from src/ffi.rs by cbindgen: cargo build --features header */
+
+#ifndef CHECK_H
+#define CHECK_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// What went wrong, if anything.
+typedef enum CheckStatus {
+  CHECK_STATUS_OK = 0,
+  CHECK_STATUS_NULL_POINTER,
+  CHECK_STATUS_NOT_UTF8,
+  CHECK_STATUS_BAD_HAND,
+  CHECK_STATUS_BAD_MULTIPLIER,
+  CHECK_STATUS_PANIC,
+} CheckStatus;
+
+// A parsed five-card hand.
+typedef struct CheckHand CheckHand;
+
+// One hold: bit i of `mask` keeps card i (0-based, in the order given to
+// check_parse), and its expected score in chips is exactly
+// `numerator / denominator` at the multiplier asked for.
+typedef struct CheckHold {
+  uint32_t mask;
+  int64_t numerator;
+  int64_t denominator;
+} CheckHold;
+
+// Holds, best first.
+typedef struct CheckHolds {
+  struct CheckHold *holds;
+  size_t len;
+} CheckHolds;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses `text` ("KH 8D 3D 4D 6D", "10d11d12d13d1d", ...) into a hand for
+// check_analyze, to be freed with check_hand_free.
+//
+// # Safety
+//
+// `text` must be a NUL-terminated string and `hand` writable.
+enum CheckStatus check_parse(const char *text, struct CheckHand **hand);
+
+// # Safety
+//
+// `hand` must be from check_parse, or null, and not freed already.
+void check_hand_free(struct CheckHand *hand);
+
+// The `top` best holds of `hand` from a fresh deck at a stake of
+// `multiplier` (1-9999), into `holds`, to be freed with check_holds_free.
+// There are 32 holds in all.
+//
+// # Safety
+//
+// `hand` must be from check_parse and `holds` writable.
+enum CheckStatus check_analyze(const struct CheckHand *hand,
+                               int32_t multiplier,
+                               size_t top,
+                               struct CheckHolds *holds);
+
+// Frees what check_analyze gave, and empties `holds`.
+//
+// # Safety
+//
+// `holds` must be from check_analyze, or null, and not freed already.
+void check_holds_free(struct CheckHolds *holds);
+
+// A static description of `status`; don't free it.
+const char *check_status_message(enum CheckStatus status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHECK_H */
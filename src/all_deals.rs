@@ -0,0 +1,292 @@
+use crate::Card;
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::NEW_DECK;
+use crate::check;
+use crate::tree_check::DeckTree;
+use crate::tree_check::PartialScore;
+use crate::tree_check::expected_score;
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use rational::Rational;
+
+pub(crate) const CATEGORIES: usize = 9;
+pub(crate) const HOLDS: usize = 1 << HAND_SIZE;
+
+const fn binomials() -> [[u32; HAND_SIZE + 1]; 53] {
+    let mut table = [[0; HAND_SIZE + 1]; 53];
+    let mut n = 0;
+    while n < 53 {
+        table[n][0] = 1;
+        let mut k = 1;
+        while k <= HAND_SIZE && k <= n {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+}
+
+const BINOMIAL: [[u32; HAND_SIZE + 1]; 53] = binomials();
+
+// Exact outcomes for every deal from a fresh 52-card deck, without
+// enumerating any draws per deal.
+//
+// Built once by scoring all C(52, 5) hands and crediting each one's category
+// to every smaller set of cards it contains. The draws for a hold H of deal
+// D are then the hands that contain H but none of the discards D \ H, which
+// inclusion-exclusion gets from the (at most 32) subsets of D:
+//
+//     N(H) = sum over H <= S <= D of (-1)^|S \ H| * count(S)
+//
+// tree_check::check enumerates up to C(47, 5) draws to answer the same
+// question for one hold, which is far too slow to run over every deal. The
+// catch is that it only covers the full deck: no dead cards.
+pub struct Table {
+    // by_size[k][colex rank of a k-card set]: how many of the 5-card hands
+    // containing that set score in each category, by `Score as usize`.
+    by_size: [Vec<[u32; CATEGORIES]>; HAND_SIZE],
+}
+
+impl Table {
+    pub fn new() -> Self {
+        let mut by_size: [Vec<[u32; CATEGORIES]>; HAND_SIZE] =
+            std::array::from_fn(|k| vec![[0; CATEGORIES]; BINOMIAL[52][k] as usize]);
+
+        for hand in (0..52).combinations(HAND_SIZE) {
+            let cards = [
+                &NEW_DECK[hand[0]],
+                &NEW_DECK[hand[1]],
+                &NEW_DECK[hand[2]],
+                &NEW_DECK[hand[3]],
+                &NEW_DECK[hand[4]],
+            ];
+            let Some(score) = check::check(&cards) else {
+                continue;
+            };
+            let category = score as usize;
+
+            // Every proper subset; `hand` is sorted, so is each subset.
+            for mask in 0..HOLDS - 1 {
+                let (size, rank) = colex(&hand, mask);
+                by_size[size][rank][category] += 1;
+            }
+        }
+
+        Table { by_size }
+    }
+
+    // Category counts over every draw, for each of the 32 holds of
+    // `full_hand` by mask (bit i set keeps the i-th card of the hand).
+    pub(crate) fn hold_counts(&self, full_hand: &FullHand) -> [[i32; CATEGORIES]; HOLDS] {
+        let cards = full_hand.cards();
+        let indices = cards.map(|card| u8::from(&card) as usize);
+
+        // count(S) for every subset S of the deal first...
+        let mut counts = [[0; CATEGORIES]; HOLDS];
+        for (mask, count) in counts.iter_mut().enumerate() {
+            if mask == HOLDS - 1 {
+                if let Some(score) = check::check(&cards) {
+                    count[score as usize] = 1;
+                }
+                continue;
+            }
+            let subset: Vec<usize> = (0..HAND_SIZE)
+                .filter(|i| mask & (1 << i) != 0)
+                .map(|i| indices[i])
+                .sorted()
+                .collect();
+            let (size, rank) = colex(&subset, (1 << subset.len()) - 1);
+            for (n, total) in count.iter_mut().zip(self.by_size[size][rank]) {
+                *n = total as i32;
+            }
+        }
+
+        // ...then the Mobius transform over supersets turns each count(H)
+        // into N(H), one card of the deal at a time.
+        for bit in 0..HAND_SIZE {
+            for mask in 0..HOLDS {
+                if mask & (1 << bit) == 0 {
+                    let superset = counts[mask | (1 << bit)];
+                    for (n, excluded) in counts[mask].iter_mut().zip(superset) {
+                        *n -= excluded;
+                    }
+                }
+            }
+        }
+
+        counts
+    }
+
+    // The same as `tree_check::check(keep, &full_hand.into())`, where `keep`
+    // is the cards of `full_hand` picked out by `mask`.
+    pub fn check(&self, full_hand: &FullHand, mask: usize) -> Vec<PartialScore> {
+        self.partial_scores(&self.hold_counts(full_hand), mask)
+    }
+
+    fn partial_scores(&self, counts: &[[i32; CATEGORIES]; HOLDS], mask: usize) -> Vec<PartialScore> {
+        DeckTree::from_counts(draws(mask), counts[mask]).into()
+    }
+
+    // Every hold of `full_hand` with its expected score, in powerset order
+    // (by size, then position in the hand).
+    pub fn expected_swap_values<'a>(
+        &self,
+        full_hand: &'a FullHand,
+        multiplier: i32,
+    ) -> Vec<(Vec<&'a Card>, Rational)> {
+        let counts = self.hold_counts(full_hand);
+        let cards = full_hand.cards();
+
+        (0..HAND_SIZE)
+            .powerset()
+            .map(|keep| {
+                let mask = keep.iter().fold(0, |mask, i| mask | (1 << i));
+                let score = expected_score(&self.partial_scores(&counts, mask), multiplier);
+                (keep.into_iter().map(|i| cards[i]).collect(), score)
+            })
+            .collect()
+    }
+
+    // The same as `tree_check::ranked_swap_values` for a fresh deck, in the
+    // same order (ties included).
+    pub fn ranked_swap_values<'a>(
+        &self,
+        full_hand: &'a FullHand,
+        multiplier: i32,
+    ) -> Vec<(Vec<&'a Card>, Rational)> {
+        let mut result = self.expected_swap_values(full_hand, multiplier);
+        result.sort_by(|(_, a), (_, b)| a.cmp(b).reverse());
+        result
+    }
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table::new()
+    }
+}
+
+// How many draws there are for the hold `mask` of a fresh-deck deal.
+pub(crate) fn draws(mask: usize) -> i32 {
+    let swaps = HAND_SIZE - mask.count_ones() as usize;
+    BINOMIAL[52 - HAND_SIZE][swaps] as i32
+}
+
+// (size, colex rank) of the cards of `sorted` picked out by `mask`: the
+// index of that set among all sets of its size, sum of C(card, position).
+fn colex(sorted: &[usize], mask: usize) -> (usize, usize) {
+    let mut size = 0;
+    let mut rank = 0;
+    for (i, &card) in sorted.iter().enumerate() {
+        if mask & (1 << i) != 0 {
+            size += 1;
+            rank += BINOMIAL[card][size] as usize;
+        }
+    }
+    (size, rank)
+}
+
+// One representative deal per suit class -- deals that only differ by
+// renaming suits play identically -- with how many of the C(52, 5) deals
+// it stands for. 134,459 classes instead of 2,598,960 deals.
+pub fn deal_classes() -> Vec<(FullHand, u32)> {
+    let suit_permutations = suit_permutations();
+    let mut classes: HashMap<u32, u32> = HashMap::new();
+
+    for hand in (0..52usize).combinations(HAND_SIZE) {
+        *classes.entry(class_key(&hand, &suit_permutations)).or_default() += 1;
+    }
+
+    classes
+        .into_iter()
+        .sorted()
+        .map(|(key, weight)| (from_class_key(key), weight))
+        .collect()
+}
+
+// The representative `deal_classes` gives for the class of `full_hand`.
+pub fn canonical(full_hand: &FullHand) -> FullHand {
+    let hand = full_hand.cards().map(|card| u8::from(&card) as usize);
+    from_class_key(class_key(&hand, &suit_permutations()))
+}
+
+fn suit_permutations() -> Vec<Vec<usize>> {
+    (0..4).permutations(4).collect()
+}
+
+// The smallest of the deal's sorted card indices, packed six bits a card,
+// over every renaming of the suits.
+fn class_key(hand: &[usize], suit_permutations: &[Vec<usize>]) -> u32 {
+    suit_permutations
+        .iter()
+        .map(|permutation| {
+            hand.iter()
+                .map(|card| permutation[card / 13] * 13 + card % 13)
+                .sorted()
+                .fold(0, |key, card| (key << 6) | card as u32)
+        })
+        .min()
+        .unwrap()
+}
+
+fn from_class_key(key: u32) -> FullHand {
+    let card = |i: u32| NEW_DECK[((key >> (6 * (4 - i))) & 0b11_1111) as usize].clone();
+    FullHand(card(0), card(1), card(2), card(3), card(4))
+}
+
+#[test]
+fn test_matches_tree_check() {
+    use crate::Rank::*;
+    use crate::Suit::*;
+    use crate::tree_check;
+
+    let table = Table::new();
+    for full_hand in [
+        FullHand(
+            Card(King, Heart),
+            Card(Eight, Diamond),
+            Card(Three, Diamond),
+            Card(Four, Diamond),
+            Card(Six, Diamond),
+        ),
+        FullHand(
+            Card(Ten, Spade),
+            Card(Ace, Spade),
+            Card(Ten, Club),
+            Card(Queen, Spade),
+            Card(King, Spade),
+        ),
+    ] {
+        let deck = (&full_hand).into();
+        assert_eq!(
+            table.ranked_swap_values(&full_hand, 3),
+            tree_check::ranked_swap_values(&full_hand, &deck, 3)
+        );
+        assert_eq!(
+            table.check(&full_hand, 0b01011),
+            tree_check::check(&[&full_hand.0, &full_hand.1, &full_hand.3], &deck)
+        );
+    }
+}
+
+#[test]
+fn test_canonical() {
+    use crate::parse_input::parse;
+
+    let a = canonical(&parse("KH 8D 3D 4D 6D").unwrap());
+    assert_eq!(a, canonical(&parse("3S 4S KC 6S 8S").unwrap()));
+    assert_eq!(a, canonical(&a));
+    assert_ne!(a, canonical(&parse("KD 8D 3D 4D 6D").unwrap()));
+}
+
+#[cfg(feature = "big-tests")]
+#[test]
+fn test_deal_classes() {
+    let classes = deal_classes();
+    assert_eq!(classes.len(), 134_459);
+    assert_eq!(classes.iter().map(|(_, weight)| weight).sum::<u32>(), 2_598_960);
+}
@@ -0,0 +1,343 @@
+use crate::all_deals;
+use crate::par::*;
+use crate::strategy::Pattern;
+use crate::strategy::RuleBased;
+
+use itertools::Itertools;
+
+// One suit class of deals, boiled down to what a chart can act on.
+pub struct Deal {
+    // How many of the C(52, 5) deals this one stands for.
+    pub weight: u32,
+    pub best: f64,
+    // For each pattern the hand contains, the expected score of the hold a
+    // rule for it picks: the first match in powerset order, as RuleBased
+    // does, so a learned chart plays exactly as it reports.
+    pub options: Vec<(Pattern, f64)>,
+}
+
+// Exact solutions for every deal from a fresh deck, via `all_deals`.
+pub fn solve(table: &all_deals::Table, multiplier: i32) -> Vec<Deal> {
+    let patterns = Pattern::all();
+
+    all_deals::deal_classes()
+        .into_par_iter()
+        .map(|(full_hand, weight)| {
+            let values = table.expected_swap_values(&full_hand, multiplier);
+            let best = values.iter().map(|(_, value)| *value).max().unwrap();
+
+            let mut options: Vec<(Pattern, f64)> = Vec::new();
+            for (keep, value) in &values {
+                for pattern in &patterns {
+                    if pattern.matches(keep) && options.iter().all(|(seen, _)| seen != pattern) {
+                        options.push((*pattern, value.decimal_value()));
+                    }
+                }
+            }
+
+            Deal {
+                weight,
+                best: best.decimal_value(),
+                options,
+            }
+        })
+        .collect()
+}
+
+pub struct Rule {
+    pub pattern: Pattern,
+    // Deals on which this is the first rule that applies.
+    pub deals: u64,
+    // Summed over those deals.
+    pub ev_loss: f64,
+}
+
+pub struct Chart {
+    pub multiplier: i32,
+    pub rules: Vec<Rule>,
+    pub deals: u64,
+    // Deals where the chart's hold scores as well as the best one.
+    pub matches: u64,
+    // Summed over all deals, as is `best`.
+    pub ev_loss: f64,
+    pub best: f64,
+}
+
+impl Chart {
+    pub fn accuracy(&self) -> f64 {
+        self.matches as f64 / self.deals as f64
+    }
+
+    pub fn ev_loss_per_hand(&self) -> f64 {
+        self.ev_loss / self.deals as f64
+    }
+
+    // Chips summed over all deals, as an average per chip wagered, so charts
+    // at different multipliers compare.
+    fn per_chip(&self, chips: f64) -> f64 {
+        chips / self.deals as f64 / self.multiplier as f64
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("# SC poker strategy (multiplier {})\n\n", self.multiplier));
+        out.push_str("Hold the cards for the first line that matches the dealt hand.\n\n");
+        out.push_str("| # | Hold | Deals | EV lost per chip |\n");
+        out.push_str("|--:|------|------:|-----------------:|\n");
+        for (i, rule) in self.rules.iter().enumerate() {
+            out.push_str(&format!(
+                "| {} | {} | {:.2}% | {:.4} |\n",
+                i + 1,
+                self.describe(rule),
+                rule.deals as f64 * 100.0 / self.deals as f64,
+                self.per_chip(rule.ev_loss),
+            ));
+        }
+        out.push_str(&format!("\n{}\n", self.summary()));
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let rows: String = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{:.2}%</td><td>{:.4}</td></tr>\n",
+                    i + 1,
+                    self.describe(rule),
+                    rule.deals as f64 * 100.0 / self.deals as f64,
+                    self.per_chip(rule.ev_loss),
+                )
+            })
+            .collect();
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>SC poker strategy (multiplier {multiplier})</title>
+<style>
+body {{ font-family: sans-serif; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #999; padding: 0.2em 0.6em; }}
+td:nth-child(1), td:nth-child(3), td:nth-child(4) {{ text-align: right; }}
+</style>
+</head>
+<body>
+<h1>SC poker strategy (multiplier {multiplier})</h1>
+<p>Hold the cards for the first line that matches the dealt hand.</p>
+<table>
+<tr><th>#</th><th>Hold</th><th>Deals</th><th>EV lost per chip</th></tr>
+{rows}</table>
+<p>{summary}</p>
+</body>
+</html>
+"#,
+            multiplier = self.multiplier,
+            summary = self.summary(),
+        )
+    }
+
+    fn describe(&self, rule: &Rule) -> String {
+        match rule.pattern {
+            Pattern::Nothing => "nothing: swap all five".to_string(),
+            pattern => pattern.to_string(),
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "Plays the best hold on {:.2}% of deals, losing {:.4} per chip wagered ({:.3}% of the {:.4} the best play returns).",
+            self.accuracy() * 100.0,
+            self.per_chip(self.ev_loss),
+            self.ev_loss * 100.0 / self.best,
+            self.per_chip(self.best),
+        )
+    }
+}
+
+impl From<&Chart> for RuleBased {
+    fn from(chart: &Chart) -> Self {
+        RuleBased {
+            rules: chart.rules.iter().map(|rule| rule.pattern).collect(),
+        }
+    }
+}
+
+// Learns the ordered rule list that loses the least expected score over
+// `deals`.
+//
+// Finding the best order outright is a feedback arc set problem, so this
+// starts from a greedy order over the pairwise costs (what putting one
+// pattern above another loses, over the deals that contain both) and then
+// moves single rules around for as long as that lowers the true loss.
+pub fn learn(deals: &[Deal], multiplier: i32) -> Chart {
+    let patterns: Vec<Pattern> = deals
+        .iter()
+        .flat_map(|deal| deal.options.iter().map(|(pattern, _)| *pattern))
+        .unique()
+        .sorted()
+        .collect();
+    let dense: Vec<Vec<Option<f64>>> = deals
+        .iter()
+        .map(|deal| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    deal.options
+                        .iter()
+                        .find(|(option, _)| option == pattern)
+                        .map(|(_, value)| *value)
+                })
+                .collect()
+        })
+        .collect();
+
+    // cost[a][b]: what ranking b above a loses.
+    let n = patterns.len();
+    let mut cost = vec![vec![0.0; n]; n];
+    for (deal, options) in deals.iter().zip(&dense) {
+        for (a, b) in (0..n).tuple_combinations() {
+            if let (Some(value_a), Some(value_b)) = (options[a], options[b]) {
+                let difference = deal.weight as f64 * (value_a - value_b);
+                if difference > 0.0 {
+                    cost[a][b] += difference;
+                } else {
+                    cost[b][a] -= difference;
+                }
+            }
+        }
+    }
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut order = Vec::with_capacity(n);
+    while !remaining.is_empty() {
+        let (i, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (i, remaining.iter().map(|&r| cost[r][c]).sum::<f64>()))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap();
+        order.push(remaining.remove(i));
+    }
+
+    let loss = |order: &[usize]| -> f64 {
+        deals
+            .iter()
+            .zip(&dense)
+            .map(|(deal, options)| {
+                let chosen = order.iter().find_map(|&p| options[p]).unwrap_or(0.0);
+                deal.weight as f64 * (deal.best - chosen)
+            })
+            .sum()
+    };
+
+    let mut current = loss(&order);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for from in 0..n {
+            for to in 0..n {
+                if from == to {
+                    continue;
+                }
+                let mut candidate = order.clone();
+                let pattern = candidate.remove(from);
+                candidate.insert(to, pattern);
+                let candidate_loss = loss(&candidate);
+                if candidate_loss < current - 1e-9 {
+                    order = candidate;
+                    current = candidate_loss;
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    let mut chart = Chart {
+        multiplier,
+        rules: order
+            .iter()
+            .map(|&p| Rule {
+                pattern: patterns[p],
+                deals: 0,
+                ev_loss: 0.0,
+            })
+            .collect(),
+        deals: 0,
+        matches: 0,
+        ev_loss: 0.0,
+        best: 0.0,
+    };
+    for (deal, options) in deals.iter().zip(&dense) {
+        let weight = deal.weight as u64;
+        chart.deals += weight;
+        chart.best += weight as f64 * deal.best;
+
+        let Some((rule, chosen)) = order
+            .iter()
+            .enumerate()
+            .find_map(|(rule, &p)| options[p].map(|value| (rule, value)))
+        else {
+            chart.ev_loss += weight as f64 * deal.best;
+            continue;
+        };
+        let loss = weight as f64 * (deal.best - chosen);
+        chart.rules[rule].deals += weight;
+        chart.rules[rule].ev_loss += loss;
+        chart.ev_loss += loss;
+        if loss <= 0.0 {
+            chart.matches += weight;
+        }
+    }
+    // Rules that never come first change nothing, so leave them off.
+    chart.rules.retain(|rule| rule.deals > 0);
+
+    chart
+}
+
+#[test]
+fn test_learn() {
+    let deal = |weight, options: &[(Pattern, f64)]| Deal {
+        weight,
+        best: options.iter().map(|(_, value)| *value).fold(0.0, f64::max),
+        options: options.to_vec(),
+    };
+    let deals = [
+        deal(
+            3,
+            &[(Pattern::Pair, 2.5), (Pattern::ToFlush(4), 2.4), (Pattern::Nothing, 1.0)],
+        ),
+        deal(
+            1,
+            &[(Pattern::ToFlush(4), 2.4), (Pattern::ToFlush(3), 1.2), (Pattern::Nothing, 1.0)],
+        ),
+        deal(4, &[(Pattern::ToFlush(3), 0.9), (Pattern::Nothing, 1.0)]),
+        // Best played by a pattern that usually isn't worth keeping.
+        deal(1, &[(Pattern::ToFlush(3), 1.3), (Pattern::Nothing, 1.0)]),
+    ];
+
+    let chart = learn(&deals, 1);
+    let rules: Vec<Pattern> = chart.rules.iter().map(|rule| rule.pattern).collect();
+    assert_eq!(rules, vec![Pattern::Pair, Pattern::ToFlush(4), Pattern::Nothing]);
+    assert_eq!(chart.deals, 9);
+    assert_eq!(chart.matches, 8);
+    assert!((chart.ev_loss - 0.3).abs() < 1e-9);
+    assert!(chart.to_markdown().contains("| 3 | nothing: swap all five | 55.56% | 0.0333 |"));
+    assert!(chart.to_html().contains("<td>pair</td>"));
+
+    // The same losses at x3 are the same per chip.
+    let tripled: Vec<Deal> = deals
+        .iter()
+        .map(|deal| Deal {
+            weight: deal.weight,
+            best: deal.best * 3.0,
+            options: deal.options.iter().map(|(pattern, value)| (*pattern, value * 3.0)).collect(),
+        })
+        .collect();
+    let chart = learn(&tripled, 3);
+    assert!(chart.to_markdown().contains("| 3 | nothing: swap all five | 55.56% | 0.0333 |"));
+}
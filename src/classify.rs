@@ -0,0 +1,149 @@
+use crate::Card;
+use crate::FullHand;
+use crate::strategy::Pattern;
+use crate::strategy::straights_containing;
+
+use std::fmt;
+
+use itertools::Itertools;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HoldClass {
+    // What the kept cards make or draw to, as the strategy rules name it.
+    pub pattern: Pattern,
+    // Kept cards beyond the ones `pattern` needs: a pair's kicker, or on
+    // `Pattern::Nothing` every card kept.
+    pub kickers: usize,
+    // Jacks, Queens, Kings and Aces among the kept cards.
+    pub high_cards: usize,
+    // Discarded cards the draw could have used (its suit, or a rank that
+    // fits its straight), which can't come back on the draw.
+    pub penalty_cards: usize,
+}
+
+// What kind of hold `keep` is, out of the dealt `full_hand`: the first of
+// `Pattern::all` that some of the kept cards make, with the rest as
+// kickers. Where a hold is several things at once (4 suited connectors are
+// a straight flush, flush and straight draw) it's named for the most
+// valuable.
+pub fn classify(full_hand: &FullHand, keep: &[&Card]) -> HoldClass {
+    let high_cards = keep.iter().filter(|card| is_high(card)).count();
+    // Nothing, at the end, matches keeping none of them.
+    let (pattern, cards) = Pattern::all()
+        .into_iter()
+        .find_map(|pattern| {
+            keep.iter()
+                .copied()
+                .powerset()
+                .find(|cards| pattern.matches(cards))
+                .map(|cards| (pattern, cards))
+        })
+        .unwrap();
+    let discarded: Vec<&Card> = full_hand
+        .cards()
+        .into_iter()
+        .filter(|card| !keep.contains(card))
+        .collect();
+
+    let helps_flush = |card: &&Card| card.suit() == cards[0].suit();
+    let helps_straight = |card: &&Card| {
+        let mut with = cards.clone();
+        with.push(card);
+        straights_containing(&with) > 0
+    };
+    let penalty_cards = match pattern {
+        Pattern::ToRoyal(_) | Pattern::ToStraightFlush(_) => discarded
+            .into_iter()
+            .filter(|card| helps_flush(card) || helps_straight(card))
+            .count(),
+        Pattern::ToFlush(_) => discarded.into_iter().filter(helps_flush).count(),
+        Pattern::ToOutsideStraight(_) | Pattern::ToInsideStraight(_) | Pattern::ToDoubleInsideStraight(_) => {
+            discarded.into_iter().filter(helps_straight).count()
+        }
+        _ => 0,
+    };
+
+    HoldClass {
+        pattern,
+        kickers: keep.len() - cards.len(),
+        high_cards,
+        penalty_cards,
+    }
+}
+
+fn is_high(card: &Card) -> bool {
+    card.rank() == 1 || card.rank() >= 11
+}
+
+impl fmt::Display for HoldClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.pattern, self.kickers) {
+            (Pattern::Nothing, 0) => write!(f, "nothing")?,
+            (Pattern::Nothing, _) => write!(f, "no draw")?,
+            (pattern, 0) => write!(f, "{pattern}")?,
+            (pattern, 1) => write!(f, "{pattern} and a kicker")?,
+            (pattern, n) => write!(f, "{pattern} and {n} kickers")?,
+        }
+        match self.penalty_cards {
+            0 => Ok(()),
+            1 => write!(f, " (1 penalty card)"),
+            n => write!(f, " ({n} penalty cards)"),
+        }
+    }
+}
+
+#[test]
+fn test_classify() {
+    use crate::parse_input::parse;
+
+    let class = |hand: &str, mask: usize| {
+        let full_hand = parse(hand).unwrap();
+        let class = classify(&full_hand, &full_hand.keep(mask));
+        (class.pattern, class.kickers)
+    };
+
+    assert_eq!(class("10h Jh Qh Kh Ah", 0b11111), (Pattern::RoyalFlush, 0));
+    assert_eq!(class("2h 2c 9d 9s 4c", 0b11111), (Pattern::TwoPair, 1));
+    assert_eq!(class("2h 2c 9d Js 4c", 0b01011), (Pattern::Pair, 1));
+    assert_eq!(class("2h 2c 2d Js 4c", 0b00111), (Pattern::ThreeOfAKind, 0));
+
+    assert_eq!(class("10h Jh Qh 3c 4c", 0b00111), (Pattern::ToRoyal(3), 0));
+    assert_eq!(class("10h Jh Qh 3c 4c", 0b01111), (Pattern::ToRoyal(3), 1));
+    assert_eq!(class("5h 6h 8h 9h 4c", 0b01111), (Pattern::ToStraightFlush(4), 0));
+    assert_eq!(class("5h 6h Qh 9h 4c", 0b01111), (Pattern::ToFlush(4), 0));
+
+    assert_eq!(class("5h 6c 7h 8s Kc", 0b01111), (Pattern::ToOutsideStraight(4), 0));
+    assert_eq!(class("Ah 2c 3h 4s Kc", 0b01111), (Pattern::ToInsideStraight(4), 0));
+    assert_eq!(class("5h 6c 8h 9s Kc", 0b01111), (Pattern::ToInsideStraight(4), 0));
+    assert_eq!(class("5h 7c 9h 3s Kc", 0b00111), (Pattern::ToDoubleInsideStraight(3), 0));
+    assert_eq!(class("2h 7c 9d Js Kc", 0b11000), (Pattern::ToInsideStraight(2), 0));
+
+    // A card or two that draw to nothing together.
+    assert_eq!(class("2h 7c 9d Js Ac", 0b10100), (Pattern::Nothing, 2));
+    assert_eq!(class("2h 7c 9d Js Ac", 0), (Pattern::Nothing, 0));
+    let full_hand = parse("10h Jh Qh Kh 4c").unwrap();
+    assert_eq!(classify(&full_hand, &full_hand.keep(0b00001)).to_string(), "no draw");
+    assert_eq!(classify(&full_hand, &full_hand.keep(0)).to_string(), "nothing");
+    assert_eq!(classify(&parse("2h 2c 9d Js 4c").unwrap(), &full_hand.keep(0)).to_string(), "nothing");
+}
+
+#[test]
+fn test_penalty_cards() {
+    use crate::parse_input::parse;
+
+    // Throwing away the 9h and 5h that would have made the flush.
+    let full_hand = parse("Jh Qh Kh 9h 5h").unwrap();
+    let class = classify(&full_hand, &full_hand.keep(0b00111));
+    assert_eq!(class.pattern, Pattern::ToRoyal(3));
+    assert_eq!(class.high_cards, 3);
+    assert_eq!(class.penalty_cards, 2);
+    assert_eq!(class.to_string(), "3 to a royal (2 penalty cards)");
+
+    let full_hand = parse("5h 6c 7h 8s 4d").unwrap();
+    let class = classify(&full_hand, &full_hand.keep(0b01111));
+    assert_eq!(class.penalty_cards, 1);
+    assert_eq!(class.to_string(), "4 to an outside straight (1 penalty card)");
+
+    let full_hand = parse("2h 2c 9d Js 4c").unwrap();
+    assert_eq!(classify(&full_hand, &full_hand.keep(0b01011)).to_string(), "pair and a kicker");
+}
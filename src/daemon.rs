@@ -0,0 +1,242 @@
+use crate::Card;
+use crate::Deck;
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::all_deals;
+use crate::server;
+use crate::server::Hand;
+use crate::tree_check;
+use crate::tree_check::PartialScore;
+
+use std::collections::HashMap;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use itertools::Itertools;
+use rational::Rational;
+use serde_json::Value;
+use serde_json::json;
+
+// `check daemon`: a long-lived process on a Unix socket that keeps what's
+// slow to work out, so short-lived clients (`check query`, scripts, an
+// overlay) get answers in well under a millisecond:
+//
+// - the all_deals table, built once at start, answers any fresh-deck hold;
+// - with dead cards, each hold's tree_check outcomes are kept by which
+//   cards are held and which are out of the deck, so they're shared across
+//   multipliers and hand orders.
+//
+// Each line a client sends is an /analyze request as in the HTTP API, and
+// it gets one line back: the /analyze response or {"error": "..."}.
+
+// Held and out-of-deck cards, one bit per card.
+type Key = (u64, u64);
+
+// Outcomes kept; past this many the cache starts over.
+const CACHE_SIZE: usize = 1 << 16;
+
+pub struct Daemon {
+    table: all_deals::Table,
+    outcomes: Mutex<HashMap<Key, Arc<Vec<PartialScore>>>>,
+}
+
+fn bits<'a>(cards: impl IntoIterator<Item = &'a Card>) -> u64 {
+    cards.into_iter().fold(0, |bits, card| bits | 1 << u8::from(&card))
+}
+
+impl Daemon {
+    // Takes a few seconds, for the table.
+    pub fn new() -> Self {
+        Daemon {
+            table: all_deals::Table::new(),
+            outcomes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // How many outcomes are cached.
+    pub fn cached(&self) -> usize {
+        self.outcomes.lock().unwrap().len()
+    }
+
+    fn outcomes(&self, full_hand: &FullHand, deck: &Deck, mask: usize) -> Arc<Vec<PartialScore>> {
+        if deck.size() == 52 - HAND_SIZE {
+            return Arc::new(self.table.check(full_hand, mask));
+        }
+        let keep = full_hand.keep(mask);
+        let key = (bits(keep.iter().copied()), !bits(&deck.cards) & ((1 << 52) - 1));
+        if let Some(outcomes) = self.outcomes.lock().unwrap().get(&key) {
+            return Arc::clone(outcomes);
+        }
+        // Worked out unlocked, so one slow hold doesn't hold up the rest.
+        let outcomes = Arc::new(tree_check::check(&keep, deck));
+        let mut cache = self.outcomes.lock().unwrap();
+        if cache.len() >= CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(key, Arc::clone(&outcomes));
+        outcomes
+    }
+
+    // The /analyze response for one request.
+    fn analyze(&self, request: &Hand) -> Result<Value, String> {
+        let (full_hand, deck) = server::deal(request).map_err(|(_, error)| error)?;
+        let multiplier = request.multiplier;
+
+        // In tree_check's order, so ties rank the same way.
+        let mut ranked: Vec<(usize, Arc<Vec<PartialScore>>, Rational)> = (0..HAND_SIZE)
+            .powerset()
+            .map(|keep| {
+                let mask = keep.iter().fold(0, |mask, i| mask | (1 << i));
+                let outcomes = self.outcomes(&full_hand, &deck, mask);
+                let value = tree_check::expected_score(&outcomes, multiplier);
+                (mask, outcomes, value)
+            })
+            .collect();
+        ranked.sort_by(|(_, _, a), (_, _, b)| a.cmp(b).reverse());
+
+        let holds: Vec<Value> = ranked
+            .iter()
+            .take(request.top.unwrap_or(ranked.len()))
+            .map(|(mask, outcomes, value)| {
+                let mut hold = server::hold_json(&full_hand, *mask, value, multiplier);
+                if request.odds {
+                    hold["odds"] = server::odds(outcomes);
+                }
+                hold
+            })
+            .collect();
+        Ok(json!({
+            "hand": server::text(&full_hand.cards()),
+            "multiplier": multiplier,
+            "holds": holds,
+        }))
+    }
+
+    // The response line for one request line.
+    pub fn answer(&self, line: &str) -> String {
+        let result = serde_json::from_str(line)
+            .map_err(|error| error.to_string())
+            .and_then(|request| self.analyze(&request));
+        match result {
+            Ok(analysis) => analysis.to_string(),
+            Err(error) => json!({ "error": error }).to_string(),
+        }
+    }
+
+    // Answers clients on `listener`, each on its own thread, for good.
+    pub fn serve(self: Arc<Self>, listener: UnixListener) {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let daemon = Arc::clone(&self);
+            thread::spawn(move || {
+                // A client hanging up mid-answer is its own business.
+                let _ = daemon.converse(stream);
+            });
+        }
+    }
+
+    fn converse(&self, stream: UnixStream) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(writer, "{}", self.answer(&line))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Daemon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// $XDG_RUNTIME_DIR/check.sock, or check-<user>.sock in the temp directory.
+pub fn default_socket() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) => PathBuf::from(runtime).join("check.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_default();
+            std::env::temp_dir().join(format!("check-{user}.sock"))
+        }
+    }
+}
+
+// Listens on `path`, clearing away a socket left by a daemon that died, but
+// not one that's still answering.
+pub fn bind(path: &Path) -> io::Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, "A daemon is already running there"));
+        }
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path)
+}
+
+// One request to the daemon on `path`, and its answer.
+pub fn query(path: &Path, request: &Value) -> io::Result<Value> {
+    let mut stream = UnixStream::connect(path)?;
+    writeln!(stream, "{request}")?;
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    serde_json::from_str(&answer).map_err(io::Error::other)
+}
+
+#[test]
+fn test_daemon() {
+    let daemon = Arc::new(Daemon::new());
+    let api = server::Api::default();
+    // The same answers as the HTTP API, fresh deck or not. The table's
+    // fractions aren't always in lowest terms, so the floats can be a place
+    // out; the exact values must match.
+    let exact = |answer: Value| -> Vec<Value> {
+        let holds = answer["holds"].as_array().unwrap().iter();
+        holds.map(|hold| json!([hold["hold"], hold["cards"], hold["class"], hold["exact"]])).collect()
+    };
+    for request in [
+        json!({ "hand": "KH 8D 3D 4D 6D", "odds": true }),
+        json!({ "hand": "KH 8D 3D 4D 6D", "multiplier": 100, "dead": ["5D", "7C"] }),
+        json!({ "hand": "2C 2D 9H 9S KS", "multiplier": 9999, "dead": ["9C"], "top": 3 }),
+    ] {
+        let answer: Value = serde_json::from_str(&daemon.answer(&request.to_string())).unwrap();
+        let expected = api.handle("POST", "/analyze", &request.to_string()).1;
+        assert_eq!(exact(answer), exact(expected), "{request}");
+    }
+    let answer: Value = serde_json::from_str(&daemon.answer(r#"{"hand": "KH 8D 3D 4D 6D", "odds": true}"#)).unwrap();
+    assert!((answer["holds"][0]["odds"]["flush"].as_f64().unwrap() - 9.0 / 47.0).abs() < 1e-12);
+
+    // Only dead-card holds are cached, by cards rather than order.
+    let cached = daemon.cached();
+    assert_eq!(cached, 64);
+    daemon.answer(r#"{"hand": "6D 4D 3D 8D KH", "multiplier": 2, "dead": ["7C 5D"]}"#);
+    assert_eq!(daemon.cached(), cached);
+
+    assert!(daemon.answer(r#"{"hand": "KH KH"}"#).contains("error"));
+    assert!(daemon.answer("nonsense").contains("error"));
+
+    let path = std::env::temp_dir().join(format!("check-daemon-{}.sock", std::process::id()));
+    let listener = bind(&path).unwrap();
+    thread::spawn({
+        let daemon = Arc::clone(&daemon);
+        move || daemon.serve(listener)
+    });
+    let answer = query(&path, &json!({ "hand": "KH 8D 3D 4D 6D", "top": 1 })).unwrap();
+    assert_eq!(answer["holds"][0]["hold"], json!([2, 3, 4, 5]));
+    assert_eq!(bind(&path).unwrap_err().kind(), io::ErrorKind::AddrInUse);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,341 @@
+use crate::Deck;
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::NEW_DECK;
+use crate::all_deals::canonical;
+use crate::classify::classify;
+use crate::strategy::Pattern;
+use crate::trainer::Grade;
+use crate::tree_check;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use itertools::Itertools;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use rational::Rational;
+
+// Hands until a missed deal comes back, doubling with each right answer.
+const FIRST_INTERVAL: u64 = 2;
+// Right at this interval and the deal is learnt: it leaves the drill.
+const LAST_INTERVAL: u64 = 64;
+
+// How many deals `deal_like` looks through, and how many of those that
+// could be the pattern it solves, before giving up.
+const TRIES: usize = 1000;
+const SOLVES: usize = 20;
+
+// A deal is a close call when the best hold beats every worse one by at
+// most this much per chip wagered.
+pub fn close_call_margin() -> Rational {
+    Rational::new(1, 10)
+}
+
+// What an item drills: one deal, by its suit class, or any deal whose best
+// hold is a pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Subject {
+    Hand(FullHand),
+    Pattern(Pattern),
+}
+
+// A deal or pattern the player got wrong, waiting to be served again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    pub subject: Subject,
+    // Served again once the drill's clock reaches this.
+    pub due: u64,
+    pub interval: u64,
+    // Times it has been got wrong.
+    pub lapses: u32,
+    // Never for a pattern.
+    pub close_call: bool,
+}
+
+// Missed deals and patterns on a spaced-repetition schedule, counted in hands played
+// rather than days so a short session still revisits them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Drill {
+    // Hands answered so far, over every session.
+    pub clock: u64,
+    pub items: Vec<Item>,
+}
+
+impl Drill {
+    // A missing file is an empty drill.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => text.parse(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Drill::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    // The deal to serve next, if any are due: close calls first, then the
+    // longest overdue.
+    pub fn due(&self) -> Option<&Item> {
+        self.items
+            .iter()
+            .filter(|item| item.due <= self.clock)
+            .min_by_key(|item| (!item.close_call, item.due))
+    }
+
+    // Reschedules `full_hand`'s class, and the pattern of its best hold,
+    // after an answer graded `grade` at a stake of `multiplier`. Mistakes on
+    // ones not yet in the drill join it.
+    pub fn record(&mut self, full_hand: &FullHand, grade: &Grade, multiplier: i32) {
+        self.clock += 1;
+        let close_call = grade.best_value - grade.runner_up_value <= close_call_margin() * multiplier;
+        self.review(Subject::Hand(canonical(full_hand)), grade.correct(), close_call);
+        self.review(Subject::Pattern(grade.best_class.pattern), grade.correct(), false);
+    }
+
+    // Puts `subject` off for another interval without counting an answer,
+    // as when no deal for a pattern turns up.
+    pub fn postpone(&mut self, subject: &Subject) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.subject == *subject) {
+            item.due = self.clock + item.interval;
+        }
+    }
+
+    fn review(&mut self, subject: Subject, correct: bool, close_call: bool) {
+        let position = self.items.iter().position(|item| item.subject == subject);
+
+        match (position, correct) {
+            (Some(i), true) => {
+                let item = &mut self.items[i];
+                if item.interval >= LAST_INTERVAL {
+                    self.items.remove(i);
+                } else {
+                    item.interval *= 2;
+                    item.due = self.clock + item.interval;
+                }
+            }
+            (Some(i), false) => {
+                let item = &mut self.items[i];
+                item.interval = FIRST_INTERVAL;
+                item.due = self.clock + FIRST_INTERVAL;
+                item.lapses += 1;
+            }
+            (None, true) => {}
+            (None, false) => self.items.push(Item {
+                subject,
+                due: self.clock + FIRST_INTERVAL,
+                interval: FIRST_INTERVAL,
+                lapses: 1,
+                close_call,
+            }),
+        }
+    }
+}
+
+// A deal of the same class as `full_hand`, with the suits renamed and the
+// cards dealt in a different order, so it isn't recognised on sight.
+pub fn disguise<R: Rng + ?Sized>(full_hand: &FullHand, rng: &mut R) -> FullHand {
+    let mut suits = [0, 1, 2, 3];
+    suits.shuffle(rng);
+    let mut cards: Vec<usize> = full_hand
+        .cards()
+        .iter()
+        .map(|card| u8::from(card) as usize)
+        .map(|card| suits[card / 13] * 13 + card % 13)
+        .collect();
+    cards.shuffle(rng);
+
+    let card = |i: usize| NEW_DECK[cards[i]].clone();
+    FullHand(card(0), card(1), card(2), card(3), card(4))
+}
+
+// A fresh deal whose best hold at `multiplier` is `pattern`, to drill a
+// pattern with deals not seen before. Only deals with some hold of that
+// shape get solved; None if nothing turns up soon enough.
+pub fn deal_like<R: Rng + ?Sized>(pattern: Pattern, multiplier: i32, rng: &mut R) -> Option<FullHand> {
+    let deck = Deck::new();
+    let mut solved = 0;
+    for _ in 0..TRIES {
+        let full_hand = deck.deal(rng);
+        if !full_hand.cards().into_iter().powerset().any(|keep| pattern.matches(&keep)) {
+            continue;
+        }
+        let ranked = tree_check::ranked_swap_values(&full_hand, &(&full_hand).into(), multiplier);
+        if classify(&full_hand, &ranked[0].0).pattern == pattern {
+            return Some(full_hand);
+        }
+        solved += 1;
+        if solved == SOLVES {
+            break;
+        }
+    }
+    None
+}
+
+// One line for the clock, then one per item:
+//
+//     clock 17
+//     due interval lapses close-call card card card card card
+//     due interval lapses 0 pattern name
+//
+// with cards as indices into a new deck and patterns by name, e.g.
+// "4 to a flush", so reordering `Pattern::all` leaves saved drills alone.
+impl std::fmt::Display for Drill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "clock {}", self.clock)?;
+        for item in &self.items {
+            let subject = match &item.subject {
+                Subject::Hand(hand) => hand.cards().iter().map(u8::from).join(" "),
+                Subject::Pattern(pattern) => pattern.to_string(),
+            };
+            writeln!(
+                f,
+                "{} {} {} {} {subject}",
+                item.due,
+                item.interval,
+                item.lapses,
+                item.close_call as u8,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Drill {
+    type Err = io::Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = |line: &str| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("bad drill line: {line:?}"))
+        };
+        let mut lines = text.lines();
+
+        let first = lines.next().unwrap_or("clock 0");
+        let clock = first
+            .strip_prefix("clock ")
+            .and_then(|clock| clock.parse().ok())
+            .ok_or_else(|| invalid(first))?;
+
+        let items = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                if words.len() < 5 {
+                    return Err(invalid(line));
+                }
+                let fields: Vec<u64> = words[..4]
+                    .iter()
+                    .map(|field| field.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid(line))?;
+                let cards: Option<Vec<usize>> = words[4..]
+                    .iter()
+                    .map(|card| card.parse().ok().filter(|&card| card < 52))
+                    .collect();
+                let subject = match cards {
+                    Some(cards) if cards.len() == HAND_SIZE => {
+                        let card = |i: usize| NEW_DECK[cards[i]].clone();
+                        Subject::Hand(FullHand(card(0), card(1), card(2), card(3), card(4)))
+                    }
+                    _ => Subject::Pattern(words[4..].join(" ").parse().map_err(|()| invalid(line))?),
+                };
+                Ok(Item {
+                    subject,
+                    due: fields[0],
+                    interval: fields[1],
+                    lapses: fields[2] as u32,
+                    close_call: fields[3] != 0,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Drill { clock, items })
+    }
+}
+
+#[test]
+fn test_drill() {
+    use crate::parse_input::parse;
+    use crate::trainer::grade;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    let full_hand = parse("KH 8D 3D 4D 6D").unwrap();
+    let deck = (&full_hand).into();
+    let wrong = grade(&full_hand, &deck, 1, 0b00001);
+    let right = grade(&full_hand, &deck, 1, 0b11110);
+    let hand = Subject::Hand(canonical(&full_hand));
+    let pattern = Subject::Pattern(Pattern::ToFlush(4));
+
+    let mut drill = Drill::default();
+    drill.record(&full_hand, &right, 1);
+    assert!(drill.items.is_empty());
+
+    // Both the deal and the pattern of its best hold join.
+    drill.record(&full_hand, &wrong, 1);
+    let subjects: Vec<&Subject> = drill.items.iter().map(|item| &item.subject).collect();
+    assert_eq!(subjects, vec![&hand, &pattern]);
+    assert_eq!(drill.due(), None);
+
+    drill.clock += FIRST_INTERVAL;
+    assert_eq!(drill.due().map(|item| &item.subject), Some(&hand));
+
+    // The same class in another guise counts as the same deal.
+    let mut rng = StdRng::seed_from_u64(0);
+    let disguised = disguise(&full_hand, &mut rng);
+    assert_eq!(canonical(&disguised), canonical(&full_hand));
+    drill.record(&disguised, &grade(&disguised, &(&disguised).into(), 1, 0), 1);
+    assert_eq!(drill.items[0].lapses, 2);
+    assert_eq!(drill.items[1].lapses, 2);
+
+    // Another 4 to a flush, played right, moves the pattern along but not
+    // the deal.
+    let other = parse("2C 9S JS 4S 6S").unwrap();
+    drill.record(&other, &grade(&other, &(&other).into(), 1, 0b11110), 1);
+    assert_eq!(drill.items[0].interval, FIRST_INTERVAL);
+    assert_eq!(drill.items[1].interval, 2 * FIRST_INTERVAL);
+
+    // Put off, the pattern stops being due.
+    drill.clock = drill.items[1].due;
+    drill.postpone(&pattern);
+    assert_eq!(drill.items[1].due, drill.clock + 2 * FIRST_INTERVAL);
+    assert_eq!(drill.items[1].lapses, 2);
+
+    let text = drill.to_string();
+    assert_eq!(text.parse::<Drill>().unwrap(), drill);
+    assert!("clock x".parse::<Drill>().is_err());
+    assert!("clock 1\n1 2 3 0 52 1 2 3 4".parse::<Drill>().is_err());
+    assert!("clock 1\n1 2 3 0 99".parse::<Drill>().is_err());
+    assert_eq!("clock 1\n1 2 3 0 4 to a flush".parse::<Drill>().unwrap().items[0].subject, pattern);
+    assert!("clock 1\n1 2 3 0 9 to a flush".parse::<Drill>().is_err());
+}
+
+#[test]
+fn test_deal_like() {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    let mut rng = StdRng::seed_from_u64(32);
+    let full_hand = deal_like(Pattern::Pair, 1, &mut rng).unwrap();
+    let ranked = tree_check::ranked_swap_values(&full_hand, &(&full_hand).into(), 1);
+    assert_eq!(classify(&full_hand, &ranked[0].0).pattern, Pattern::Pair);
+}
+
+#[test]
+fn test_close_calls_first() {
+    let item = |due, close_call| Item {
+        subject: Subject::Hand(crate::parse_input::parse("KH 8D 3D 4D 6D").unwrap()),
+        due,
+        interval: FIRST_INTERVAL,
+        lapses: 1,
+        close_call,
+    };
+    let drill = Drill {
+        clock: 10,
+        items: vec![item(3, false), item(8, true), item(11, true)],
+    };
+    assert_eq!(drill.due(), Some(&drill.items[1]));
+}
@@ -0,0 +1,218 @@
+use crate::Card;
+use crate::Score;
+use crate::check;
+use crate::history::Entry;
+use crate::hold;
+use crate::parse_input;
+use crate::stats::chi_square_p;
+use crate::stats::two_sided_p;
+use crate::tree_check;
+
+use std::collections::BTreeMap;
+
+// Bins expected to hold fewer than this many are pooled, as the chi-square
+// approximation wants.
+const MIN_EXPECTED: f64 = 5.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Test {
+    pub name: String,
+    pub samples: u64,
+    pub statistic: f64,
+    // For the chi-square tests; the runs test's statistic is a z-score.
+    pub dof: Option<usize>,
+    // Chance of a result at least this extreme from a fair deal. Small
+    // values are evidence against the RNG, with the usual caveat that a
+    // handful of tests will throw up the odd one by chance.
+    pub p_value: f64,
+}
+
+// Every test the log has enough data for:
+//
+// - final categories of played-out hands, chi-square against the exact
+//   chances of each hand's own hold (as `tree_check` has them)
+// - a Wald-Wolfowitz runs test on which hands paid, for streakiness
+// - suits and ranks of every card dealt or drawn, chi-square against
+//   uniform
+//
+// Errs on the first entry with cards that don't parse.
+#[allow(clippy::result_unit_err)]
+pub fn analyse(entries: &[Entry]) -> Result<Vec<Test>, ()> {
+    let mut tests = Vec::new();
+    let mut observed: BTreeMap<Option<Score>, f64> = BTreeMap::new();
+    let mut expected: BTreeMap<Option<Score>, f64> = BTreeMap::new();
+    let mut wins = Vec::new();
+    let mut suits = [0.0; 4];
+    let mut ranks = [0.0; 13];
+
+    for entry in entries {
+        let full_hand = entry.full_hand()?;
+        let drawn = drawn_cards(entry)?;
+        for card in full_hand.cards().into_iter().chain(&drawn) {
+            suits[u8::from(*card.suit()) as usize] += 1.0;
+            ranks[card.rank() - 1] += 1.0;
+        }
+
+        let (Some(held), Some(draw)) = (&entry.held, &entry.draw) else {
+            continue;
+        };
+        let keep = full_hand.keep(hold::mask(held)?);
+        let score = check::check(&parse_input::parse(draw)?.cards());
+
+        let mut nothing = 1.0;
+        for outcome in tree_check::check(&keep, &entry.deck()?) {
+            let probability = outcome.probability().decimal_value();
+            *expected.entry(Some(outcome.category())).or_default() += probability;
+            nothing -= probability;
+        }
+        *expected.entry(None).or_default() += nothing;
+        *observed.entry(score).or_default() += 1.0;
+        wins.push(score.is_some());
+    }
+
+    let bins: Vec<(f64, f64)> = expected
+        .iter()
+        .map(|(category, &e)| (observed.get(category).copied().unwrap_or(0.0), e))
+        .collect();
+    tests.extend(chi_square("final categories", &bins));
+    tests.extend(runs("runs of wins and losses", &wins));
+
+    let uniform = |counts: &[f64]| -> Vec<(f64, f64)> {
+        let each = counts.iter().sum::<f64>() / counts.len() as f64;
+        counts.iter().map(|&count| (count, each)).collect()
+    };
+    tests.extend(chi_square("suits", &uniform(&suits)));
+    tests.extend(chi_square("ranks", &uniform(&ranks)));
+
+    Ok(tests)
+}
+
+// Pearson's test over (observed, expected) bins, pooling the least likely
+// until every bin expects at least MIN_EXPECTED. None if that leaves fewer
+// than two.
+fn chi_square(name: &str, bins: &[(f64, f64)]) -> Option<Test> {
+    let mut bins: Vec<(f64, f64)> = bins.iter().copied().filter(|(_, e)| *e > 0.0).collect();
+    bins.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    while bins.len() >= 2 && bins[0].1 < MIN_EXPECTED {
+        let (o, e) = bins.remove(0);
+        bins[0].0 += o;
+        bins[0].1 += e;
+        bins.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    }
+    if bins.len() < 2 || bins[0].1 < MIN_EXPECTED {
+        return None;
+    }
+
+    let statistic = bins.iter().map(|(o, e)| (o - e).powi(2) / e).sum();
+    let dof = bins.len() - 1;
+    Some(Test {
+        name: name.to_string(),
+        samples: bins.iter().map(|(o, _)| o).sum::<f64>() as u64,
+        statistic,
+        dof: Some(dof),
+        p_value: chi_square_p(statistic, dof),
+    })
+}
+
+// Too few runs means streaks, too many means the results alternate more
+// than chance would. Each hand's chance of paying depends on its hold, so
+// this treats wins as exchangeable -- fine for spotting streaks, not a test
+// of the payout rate (the category test covers that).
+fn runs(name: &str, outcomes: &[bool]) -> Option<Test> {
+    let n = outcomes.len() as f64;
+    let wins = outcomes.iter().filter(|&&won| won).count() as f64;
+    let losses = n - wins;
+    if wins == 0.0 || losses == 0.0 {
+        return None;
+    }
+
+    let runs = 1 + outcomes.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    let mean = 2.0 * wins * losses / n + 1.0;
+    let variance = 2.0 * wins * losses * (2.0 * wins * losses - n) / (n * n * (n - 1.0));
+    if variance <= 0.0 {
+        return None;
+    }
+    let z = (runs as f64 - mean) / variance.sqrt();
+
+    Some(Test {
+        name: name.to_string(),
+        samples: outcomes.len() as u64,
+        statistic: z,
+        dof: None,
+        p_value: two_sided_p(z),
+    })
+}
+
+// The cards of `entry`'s draw that weren't in its deal.
+fn drawn_cards(entry: &Entry) -> Result<Vec<Card>, ()> {
+    let dealt = entry.full_hand()?;
+    let Some(draw) = &entry.draw else {
+        return Ok(Vec::new());
+    };
+    Ok(parse_input::parse_cards(draw)?
+        .into_iter()
+        .filter(|card| !dealt.cards().contains(&card))
+        .collect())
+}
+
+#[test]
+fn test_chi_square() {
+    // A fair die rolled 60 times, and a loaded one.
+    let fair = [9.0, 11.0, 10.0, 8.0, 12.0, 10.0].map(|o| (o, 10.0));
+    let test = chi_square("die", &fair).unwrap();
+    assert!((test.statistic - 1.0).abs() < 1e-12);
+    assert_eq!(test.dof, Some(5));
+    assert!(test.p_value > 0.9);
+
+    let loaded = [30.0, 6.0, 6.0, 6.0, 6.0, 6.0].map(|o| (o, 10.0));
+    assert!(chi_square("die", &loaded).unwrap().p_value < 1e-6);
+
+    // The two unlikely bins pool into one.
+    let pooled = chi_square("pooled", &[(1.0, 2.0), (4.0, 3.0), (20.0, 20.0)]).unwrap();
+    assert_eq!(pooled.dof, Some(1));
+    assert_eq!(pooled.samples, 25);
+    assert!(chi_square("tiny", &[(1.0, 1.0), (1.0, 1.0)]).is_none());
+}
+
+#[test]
+fn test_runs() {
+    let alternating: Vec<bool> = (0..40).map(|i| i % 2 == 0).collect();
+    assert!(runs("runs", &alternating).unwrap().statistic > 0.0);
+    assert!(runs("runs", &alternating).unwrap().p_value < 1e-6);
+
+    let streaky: Vec<bool> = (0..40).map(|i| i < 20).collect();
+    assert!(runs("runs", &streaky).unwrap().statistic < 0.0);
+    assert!(runs("runs", &streaky).unwrap().p_value < 1e-6);
+
+    assert!(runs("runs", &[true, true]).is_none());
+}
+
+#[test]
+fn test_analyse() {
+    use crate::parse_input::parse;
+
+    let full_hand = parse("KH 8D 3D 4D 6D").unwrap();
+    let entries: Vec<Entry> = (0..40)
+        .map(|i| {
+            let mut entry = Entry::new(&full_hand, 1, &[], 0b11110);
+            entry.held = Some(vec![1]);
+            let draw = if i % 3 == 0 { "KH KD 2S 5C JD" } else { "KH 9C 2S 5C JD" };
+            entry.draw = Some(draw.to_string());
+            entry
+        })
+        .collect();
+
+    let tests = analyse(&entries).unwrap();
+    let names: Vec<&str> = tests.iter().map(|test| test.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["final categories", "runs of wins and losses", "suits", "ranks"]
+    );
+    assert!(tests.iter().all(|test| (0.0..=1.0).contains(&test.p_value)));
+    // The same cards forty times over is anything but uniform.
+    assert!(tests[3].p_value < 1e-6);
+    assert_eq!(
+        drawn_cards(&entries[0]).unwrap(),
+        parse_input::parse_cards("KD 2S 5C JD").unwrap()
+    );
+}
@@ -0,0 +1,188 @@
+use crate::FullHand;
+use crate::MAX_MULTIPLIER;
+use crate::parse_input;
+use crate::tree_check;
+
+use std::ffi::CStr;
+use std::ffi::c_char;
+use std::panic;
+use std::ptr;
+
+// The C API, declared in include/check.h (regenerate with
+// `cargo build --features header`). Every call returns a status; results
+// come back through out-pointers and are freed with the matching
+// check_*_free. Doc comments here end up in the header.
+
+/// What went wrong, if anything.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok = 0,
+    NullPointer,
+    NotUtf8,
+    BadHand,
+    BadMultiplier,
+    // A bug on our side, caught before it crossed into C.
+    Panic,
+}
+
+/// A parsed five-card hand.
+pub struct CheckHand(FullHand);
+
+/// One hold: bit i of `mask` keeps card i (0-based, in the order given to
+/// check_parse), and its expected score in chips is exactly
+/// `numerator / denominator` at the multiplier asked for.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CheckHold {
+    pub mask: u32,
+    pub numerator: i64,
+    pub denominator: i64,
+}
+
+/// Holds, best first.
+#[repr(C)]
+#[derive(Debug)]
+pub struct CheckHolds {
+    pub holds: *mut CheckHold,
+    pub len: usize,
+}
+
+fn guard(f: impl FnOnce() -> CheckStatus + panic::UnwindSafe) -> CheckStatus {
+    panic::catch_unwind(f).unwrap_or(CheckStatus::Panic)
+}
+
+/// Parses `text` ("KH 8D 3D 4D 6D", "10d11d12d13d1d", ...) into a hand for
+/// check_analyze, to be freed with check_hand_free.
+///
+/// # Safety
+///
+/// `text` must be a NUL-terminated string and `hand` writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn check_parse(text: *const c_char, hand: *mut *mut CheckHand) -> CheckStatus {
+    if text.is_null() || hand.is_null() {
+        return CheckStatus::NullPointer;
+    }
+    let text = unsafe { CStr::from_ptr(text) };
+    guard(|| {
+        let Ok(text) = text.to_str() else {
+            return CheckStatus::NotUtf8;
+        };
+        let Ok(full_hand) = parse_input::parse(text) else {
+            return CheckStatus::BadHand;
+        };
+        unsafe { *hand = Box::into_raw(Box::new(CheckHand(full_hand))) };
+        CheckStatus::Ok
+    })
+}
+
+/// # Safety
+///
+/// `hand` must be from check_parse, or null, and not freed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn check_hand_free(hand: *mut CheckHand) {
+    if !hand.is_null() {
+        drop(unsafe { Box::from_raw(hand) });
+    }
+}
+
+/// The `top` best holds of `hand` from a fresh deck at a stake of
+/// `multiplier` (1-9999), into `holds`, to be freed with check_holds_free.
+/// There are 32 holds in all.
+///
+/// # Safety
+///
+/// `hand` must be from check_parse and `holds` writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn check_analyze(
+    hand: *const CheckHand,
+    multiplier: i32,
+    top: usize,
+    holds: *mut CheckHolds,
+) -> CheckStatus {
+    if hand.is_null() || holds.is_null() {
+        return CheckStatus::NullPointer;
+    }
+    if !(1..=MAX_MULTIPLIER).contains(&multiplier) {
+        return CheckStatus::BadMultiplier;
+    }
+    let full_hand = unsafe { &(*hand).0 };
+    guard(|| {
+        let ranked: Box<[CheckHold]> =
+            tree_check::ranked_swap_values(full_hand, &full_hand.into(), multiplier)
+                .iter()
+                .take(top)
+                .map(|(keep, value)| CheckHold {
+                    mask: full_hand.mask(keep) as u32,
+                    numerator: value.numerator() as i64,
+                    denominator: value.denominator() as i64,
+                })
+                .collect();
+        let len = ranked.len();
+        unsafe {
+            *holds = CheckHolds {
+                holds: Box::into_raw(ranked) as *mut CheckHold,
+                len,
+            }
+        };
+        CheckStatus::Ok
+    })
+}
+
+/// Frees what check_analyze gave, and empties `holds`.
+///
+/// # Safety
+///
+/// `holds` must be from check_analyze, or null, and not freed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn check_holds_free(holds: *mut CheckHolds) {
+    if holds.is_null() {
+        return;
+    }
+    let holds = unsafe { &mut *holds };
+    if !holds.holds.is_null() {
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(holds.holds, holds.len)) });
+    }
+    holds.holds = ptr::null_mut();
+    holds.len = 0;
+}
+
+/// A static description of `status`; don't free it.
+#[unsafe(no_mangle)]
+pub extern "C" fn check_status_message(status: CheckStatus) -> *const c_char {
+    let message: &CStr = match status {
+        CheckStatus::Ok => c"ok",
+        CheckStatus::NullPointer => c"null pointer",
+        CheckStatus::NotUtf8 => c"text isn't UTF-8",
+        CheckStatus::BadHand => c"not a hand of five different cards",
+        CheckStatus::BadMultiplier => c"multiplier isn't 1-9999",
+        CheckStatus::Panic => c"internal error",
+    };
+    message.as_ptr()
+}
+
+#[test]
+fn test_ffi() {
+    let mut hand = ptr::null_mut();
+    assert_eq!(unsafe { check_parse(c"KH 8D 3D 4D 6D".as_ptr(), &mut hand) }, CheckStatus::Ok);
+
+    let mut holds = CheckHolds {
+        holds: ptr::null_mut(),
+        len: 0,
+    };
+    assert_eq!(unsafe { check_analyze(hand, 1, 3, &mut holds) }, CheckStatus::Ok);
+    assert_eq!(holds.len, 3);
+    let best = unsafe { *holds.holds };
+    assert_eq!(best.mask, 0b11110);
+    assert_eq!((best.numerator, best.denominator), (114, 47));
+
+    assert_eq!(unsafe { check_analyze(hand, 0, 3, &mut holds) }, CheckStatus::BadMultiplier);
+    unsafe { check_holds_free(&mut holds) };
+    assert!(holds.holds.is_null());
+    unsafe { check_hand_free(hand) };
+
+    let mut hand = ptr::null_mut();
+    assert_eq!(unsafe { check_parse(c"KH KH".as_ptr(), &mut hand) }, CheckStatus::BadHand);
+    assert_eq!(unsafe { check_parse(ptr::null(), &mut hand) }, CheckStatus::NullPointer);
+    assert!(hand.is_null());
+}
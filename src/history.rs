@@ -0,0 +1,235 @@
+use crate::Card;
+use crate::Deck;
+use crate::FullHand;
+use crate::hold;
+use crate::parse_input;
+use crate::trainer::Grade;
+use crate::trainer::grade;
+
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::Utc;
+use rational::Rational;
+use serde::Deserialize;
+use serde::Serialize;
+
+// One analysed hand, as a line of the JSON-lines history file. Cards are
+// kept as text parse_input reads back ("KH 8D 3D 4D 6D"), holds as the slot
+// positions 1-5.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub time: DateTime<Utc>,
+    pub deal: String,
+    pub multiplier: i32,
+    // Cards known to be out of the deck besides the deal.
+    #[serde(default)]
+    pub dead: Vec<String>,
+    pub recommended: Vec<usize>,
+    // The rest are only known once the hand has been played out.
+    pub held: Option<Vec<usize>>,
+    // The hand after the draw.
+    pub draw: Option<String>,
+    pub payout: Option<i32>,
+}
+
+impl Entry {
+    pub fn new(full_hand: &FullHand, multiplier: i32, dead: &[Card], recommended: usize) -> Self {
+        Entry {
+            time: Utc::now(),
+            deal: cards_text(full_hand),
+            multiplier,
+            dead: dead.iter().map(Card::to_string).collect(),
+            recommended: hold::positions(recommended),
+            held: None,
+            draw: None,
+            payout: None,
+        }
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn full_hand(&self) -> Result<FullHand, ()> {
+        parse_input::parse(&self.deal)
+    }
+
+    // The deck the draw came from: everything but the deal and dead cards.
+    #[allow(clippy::result_unit_err)]
+    pub fn deck(&self) -> Result<Deck, ()> {
+        let mut deck: Deck = (&self.full_hand()?).into();
+        for card in &self.dead {
+            for card in parse_input::parse_cards(card)? {
+                deck = deck.take_card(&card);
+            }
+        }
+        Ok(deck)
+    }
+}
+
+pub fn cards_text(full_hand: &FullHand) -> String {
+    full_hand.cards().map(|card| card.to_string()).join(" ")
+}
+
+pub fn append(path: &Path, entry: &Entry) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+}
+
+pub fn load(path: &Path) -> io::Result<Vec<Entry>> {
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|error| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {error}", i + 1))
+            })
+        })
+        .collect()
+}
+
+// Which entries to replay. Dates are inclusive and UTC; `pattern` is
+// matched against the name of the best hold's class, e.g. "flush" or
+// "inside straight".
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub pattern: Option<String>,
+}
+
+impl Filter {
+    fn dates(&self, entry: &Entry) -> bool {
+        let date = entry.time.date_naive();
+        self.since.is_none_or(|since| date >= since) && self.until.is_none_or(|until| date <= until)
+    }
+
+    fn pattern(&self, grade: &Grade) -> bool {
+        self.pattern.as_ref().is_none_or(|pattern| {
+            grade.best_class.pattern.to_string().to_lowercase().contains(&pattern.to_lowercase())
+        })
+    }
+}
+
+// A logged hold that scored less than the best one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deviation {
+    // Into the entries replayed.
+    pub entry: usize,
+    pub grade: Grade,
+    // Over every deviation up to and including this one.
+    pub cumulative_ev_lost: Rational,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    // Played-out hands that pass the filter.
+    pub hands: u64,
+    pub deviations: Vec<Deviation>,
+    pub ev_lost: Rational,
+}
+
+// Re-solves every played-out entry that passes `filter` and lists those
+// where the hold made wasn't the best. EV is in chips, at each entry's own
+// multiplier. Errs on the first entry with cards that don't parse.
+#[allow(clippy::result_unit_err)]
+pub fn replay(entries: &[Entry], filter: &Filter) -> Result<Replay, ()> {
+    let mut replay = Replay {
+        hands: 0,
+        deviations: Vec::new(),
+        ev_lost: Rational::zero(),
+    };
+
+    for (i, entry) in entries.iter().enumerate() {
+        let Some(held) = &entry.held else {
+            continue;
+        };
+        if !filter.dates(entry) {
+            continue;
+        }
+
+        let full_hand = entry.full_hand()?;
+        let grade = grade(&full_hand, &entry.deck()?, entry.multiplier, hold::mask(held)?);
+        if !filter.pattern(&grade) {
+            continue;
+        }
+
+        replay.hands += 1;
+        if !grade.correct() {
+            replay.ev_lost += grade.ev_lost();
+            replay.deviations.push(Deviation {
+                entry: i,
+                grade,
+                cumulative_ev_lost: replay.ev_lost,
+            });
+        }
+    }
+
+    Ok(replay)
+}
+
+#[test]
+fn test_history() {
+    let path = std::env::temp_dir().join(format!("check-history-{}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let full_hand = parse_input::parse("KH 8D 3D 4D 6D").unwrap();
+    let mut right = Entry::new(&full_hand, 1, &[], 0b11110);
+    right.held = Some(vec![2, 3, 4, 5]);
+    let mut wrong = Entry::new(&full_hand, 2, &[], 0b11110);
+    wrong.held = Some(vec![1]);
+    wrong.draw = Some("KH 9C 2S 5C JD".to_string());
+    wrong.payout = Some(0);
+    let unplayed = Entry::new(&full_hand, 1, &[], 0b11110);
+
+    for entry in [&right, &wrong, &unplayed] {
+        append(&path, entry).unwrap();
+    }
+    let entries = load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(entries, vec![right, wrong, unplayed]);
+
+    let all = replay(&entries, &Filter::default()).unwrap();
+    assert_eq!(all.hands, 2);
+    assert_eq!(all.deviations.len(), 1);
+    assert_eq!(all.deviations[0].entry, 1);
+    assert_eq!(all.deviations[0].grade.best, 0b11110);
+    assert_eq!(all.ev_lost, all.deviations[0].grade.ev_lost());
+
+    let straights = Filter {
+        pattern: Some("Straight".to_string()),
+        ..Filter::default()
+    };
+    assert_eq!(replay(&entries, &straights).unwrap().hands, 0);
+
+    let tomorrow = Filter {
+        since: Some(Utc::now().date_naive().succ_opt().unwrap()),
+        ..Filter::default()
+    };
+    assert_eq!(replay(&entries, &tomorrow).unwrap().hands, 0);
+}
+
+#[test]
+fn test_replay_dead() {
+    // Four to an open-ended straight flush with 2S and 7S dead: it can
+    // only make the straight or the flush now.
+    let full_hand = parse_input::parse("3S 4S 5S 6S KD").unwrap();
+    let dead = parse_input::parse_cards("2S 7S").unwrap();
+    let mut entry = Entry::new(&full_hand, 1, &dead, 0b01111);
+    entry.held = Some(vec![1, 2, 3, 4]);
+    assert_eq!(entry.dead, vec!["2S", "7S"]);
+
+    let line = serde_json::to_string(&entry).unwrap();
+    let entry: Entry = serde_json::from_str(&line).unwrap();
+    let deck = entry.deck().unwrap();
+    assert_eq!(deck.size(), 45);
+
+    let replayed = replay(&[entry], &Filter::default()).unwrap();
+    assert_eq!(replayed.hands, 1);
+    let graded = grade(&full_hand, &deck, 1, 0b01111);
+    assert_ne!(graded, grade(&full_hand, &(&full_hand).into(), 1, 0b01111));
+    assert_eq!(replayed.ev_lost, graded.ev_lost());
+}
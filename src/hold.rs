@@ -0,0 +1,74 @@
+use crate::HAND_SIZE;
+
+use std::fmt;
+
+// Card slots 1-5 held by `mask` (bit i for the (i+1)th card dealt), left to
+// right as they sit on the game screen.
+pub fn positions(mask: usize) -> Vec<usize> {
+    (0..HAND_SIZE).filter(|i| mask & (1 << i) != 0).map(|i| i + 1).collect()
+}
+
+// The mask for slots 1-5, the other way round from `positions`.
+#[allow(clippy::result_unit_err)]
+pub fn mask(positions: &[usize]) -> Result<usize, ()> {
+    positions.iter().try_fold(0, |mask, &slot| match slot {
+        1..=HAND_SIZE => Ok(mask | (1 << (slot - 1))),
+        _ => Err(()),
+    })
+}
+
+// "HOLD: x _ x x _", the hold as the game screen shows it.
+pub fn strip(mask: usize) -> String {
+    let slots: Vec<&str> = (0..HAND_SIZE)
+        .map(|i| if mask & (1 << i) != 0 { "x" } else { "_" })
+        .collect();
+    format!("HOLD: {}", slots.join(" "))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Button {
+    Right,
+    // Toggles hold on the card under the cursor.
+    Confirm,
+    // Draws replacements for every card not held.
+    Draw,
+}
+
+// Presses that make the hold in `mask` from a fresh deal, with the cursor
+// starting on the first card and nothing held yet.
+pub fn buttons(mask: usize) -> Vec<Button> {
+    let mut presses = Vec::new();
+    let mut cursor = 1;
+    for slot in positions(mask) {
+        presses.extend(std::iter::repeat_n(Button::Right, slot - cursor));
+        presses.push(Button::Confirm);
+        cursor = slot;
+    }
+    presses.push(Button::Draw);
+    presses
+}
+
+impl fmt::Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Button::Right => write!(f, "Right"),
+            Button::Confirm => write!(f, "Confirm"),
+            Button::Draw => write!(f, "Draw"),
+        }
+    }
+}
+
+#[test]
+fn test_hold() {
+    use Button::*;
+
+    assert_eq!(positions(0b01101), vec![1, 3, 4]);
+    assert_eq!(mask(&[1, 3, 4]), Ok(0b01101));
+    assert_eq!(mask(&[6]), Err(()));
+    assert_eq!(strip(0b01101), "HOLD: x _ x x _");
+    assert_eq!(strip(0), "HOLD: _ _ _ _ _");
+
+    assert_eq!(buttons(0b01101), vec![Confirm, Right, Right, Confirm, Right, Confirm, Draw]);
+    assert_eq!(buttons(0b10000), vec![Right, Right, Right, Right, Confirm, Draw]);
+    assert_eq!(buttons(0), vec![Draw]);
+}
@@ -1,6 +1,41 @@
 #[cfg(test)]
 mod test;
 
+pub mod all_deals;
+pub mod chart;
+pub mod classify;
+#[cfg(all(unix, feature = "io"))]
+pub mod daemon;
+#[cfg(feature = "io")]
+pub mod drill;
+#[cfg(feature = "io")]
+pub mod fairness;
+pub mod ffi;
+#[cfg(feature = "io")]
+pub mod history;
+pub mod hold;
+pub mod luck;
+pub mod monte_carlo;
+pub mod multiplier;
+mod par;
+pub mod paytable;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod rpc;
+#[cfg(feature = "screenshot")]
+pub mod screenshot;
+pub mod server;
+pub mod simulate;
+pub mod stats;
+pub mod strategy;
+pub mod trainer;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "io")]
+pub mod watch;
+
 use crate::Suit::*;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -105,6 +140,27 @@ impl std::fmt::Debug for Card {
     }
 }
 
+// The plain text form parse_input reads back, e.g. "10D" or "KH".
+impl std::fmt::Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let rank = match self.rank() {
+            1 => "A".to_string(),
+            11 => "J".to_string(),
+            12 => "Q".to_string(),
+            13 => "K".to_string(),
+            rank => rank.to_string(),
+        };
+        let suit = match self.suit() {
+            Club => "C",
+            Diamond => "D",
+            Heart => "H",
+            Spade => "S",
+        };
+
+        write!(f, "{}{}", rank, suit)
+    }
+}
+
 impl From<&&Card> for u8 {
     fn from(item: &&Card) -> Self {
         match item {
@@ -246,6 +302,35 @@ impl Deck {
         }
         self
     }
+
+    // Draws `n` distinct cards uniformly at random without taking them, so
+    // one deck can be sampled from over and over (e.g. by monte_carlo).
+    pub fn sample<'a, R: rand::Rng + ?Sized>(
+        &'a self,
+        n: usize,
+        rng: &mut R,
+    ) -> impl Iterator<Item = &'a Card> {
+        rand::seq::index::sample(rng, self.size(), n)
+            .into_iter()
+            .map(|i| &self.cards[i])
+    }
+
+    pub fn deal<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> FullHand {
+        let cards: Vec<&Card> = self.sample(HAND_SIZE, rng).collect();
+        FullHand(
+            cards[0].clone(),
+            cards[1].clone(),
+            cards[2].clone(),
+            cards[3].clone(),
+            cards[4].clone(),
+        )
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Deck::new()
+    }
 }
 
 impl From<Vec<Card>> for Deck {
@@ -265,8 +350,8 @@ impl From<&FullHand> for Deck {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum Score {
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Score {
     Pair,
     TwoPair,
     ThreeOfAKind,
@@ -278,6 +363,21 @@ enum Score {
     RoyalFlush,
 }
 
+impl Score {
+    // Lowest paying first, as the discriminants run.
+    pub const ALL: [Score; 9] = [
+        Score::Pair,
+        Score::TwoPair,
+        Score::ThreeOfAKind,
+        Score::Straight,
+        Score::Flush,
+        Score::FullHouse,
+        Score::FourOfAKind,
+        Score::StraightFlush,
+        Score::RoyalFlush,
+    ];
+}
+
 const PAIR_SCORE: u16 = 2;
 const TWO_PAIR_SCORE: u16 = 3;
 const THREE_OF_A_KIND_SCORE: u16 = 4;
@@ -288,6 +388,9 @@ const FOUR_OF_A_KIND_SCORE: u16 = 50;
 const STRAIGHT_FLUSH_SCORE: u16 = 100;
 const ROYAL_FLUSH_SCORE: u16 = 500;
 
+// The most chips a single payout can be worth, whatever the multiplier.
+const MAX_MULTIPLIER: i32 = 9999;
+
 impl From<Score> for u16 {
     fn from(score: Score) -> u16 {
         match score {
@@ -304,10 +407,61 @@ impl From<Score> for u16 {
     }
 }
 
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let name = match self {
+            Score::Pair => "pair",
+            Score::TwoPair => "two pair",
+            Score::ThreeOfAKind => "three of a kind",
+            Score::Straight => "straight",
+            Score::Flush => "flush",
+            Score::FullHouse => "full house",
+            Score::FourOfAKind => "four of a kind",
+            Score::StraightFlush => "straight flush",
+            Score::RoyalFlush => "royal flush",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// By the names Display gives, any case, with '-' or '_' for spaces.
+impl std::str::FromStr for Score {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let name = input.trim().to_lowercase().replace(['-', '_'], " ");
+        Score::ALL.into_iter().find(|score| score.to_string() == name).ok_or(())
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct FullHand(pub Card, pub Card, pub Card, pub Card, pub Card);
 const HAND_SIZE: usize = size_of::<FullHand>() / size_of::<Card>();
 
+impl FullHand {
+    pub fn cards(&self) -> [&Card; HAND_SIZE] {
+        [&self.0, &self.1, &self.2, &self.3, &self.4]
+    }
+
+    // Bit i set when the i-th card of the hand is kept.
+    pub fn mask(&self, keep: &[&Card]) -> usize {
+        self.cards()
+            .iter()
+            .enumerate()
+            .filter(|(_, card)| keep.contains(card))
+            .fold(0, |mask, (i, _)| mask | (1 << i))
+    }
+
+    pub fn keep(&self, mask: usize) -> Vec<&Card> {
+        self.cards()
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, card)| card)
+            .collect()
+    }
+}
+
 mod check {
     use crate::Card;
     use crate::Score;
@@ -399,25 +553,17 @@ mod check {
 pub mod tree_check {
     use crate::Card;
     use crate::Deck;
-    use crate::FLUSH_SCORE;
-    use crate::FOUR_OF_A_KIND_SCORE;
-    use crate::FULL_HOUSE_SCORE;
     use crate::FullHand;
     use crate::HAND_SIZE;
-    use crate::PAIR_SCORE;
-    use crate::ROYAL_FLUSH_SCORE;
-    use crate::STRAIGHT_FLUSH_SCORE;
-    use crate::STRAIGHT_SCORE;
     use crate::Score;
-    use crate::THREE_OF_A_KIND_SCORE;
-    use crate::TWO_PAIR_SCORE;
     use crate::check;
+    use crate::par::*;
+    use crate::paytable::Paytable;
 
     use std::ops::Add;
 
     use itertools::Itertools;
     use rational::Rational;
-    use rayon::prelude::*;
 
     #[derive(Debug, PartialEq)]
     pub enum PartialScore {
@@ -433,17 +579,32 @@ pub mod tree_check {
     }
 
     impl PartialScore {
-        fn score(&self) -> Rational {
+        pub fn category(&self) -> Score {
             match self {
-                PartialScore::Pair(s) => s * PAIR_SCORE,
-                PartialScore::TwoPair(s) => s * TWO_PAIR_SCORE,
-                PartialScore::ThreeOfAKind(s) => s * THREE_OF_A_KIND_SCORE,
-                PartialScore::Straight(s) => s * STRAIGHT_SCORE,
-                PartialScore::Flush(s) => s * FLUSH_SCORE,
-                PartialScore::FullHouse(s) => s * FULL_HOUSE_SCORE,
-                PartialScore::FourOfAKind(s) => s * FOUR_OF_A_KIND_SCORE,
-                PartialScore::StraightFlush(s) => s * STRAIGHT_FLUSH_SCORE,
-                PartialScore::RoyalFlush(s) => s * ROYAL_FLUSH_SCORE,
+                PartialScore::Pair(_) => Score::Pair,
+                PartialScore::TwoPair(_) => Score::TwoPair,
+                PartialScore::ThreeOfAKind(_) => Score::ThreeOfAKind,
+                PartialScore::Straight(_) => Score::Straight,
+                PartialScore::Flush(_) => Score::Flush,
+                PartialScore::FullHouse(_) => Score::FullHouse,
+                PartialScore::FourOfAKind(_) => Score::FourOfAKind,
+                PartialScore::StraightFlush(_) => Score::StraightFlush,
+                PartialScore::RoyalFlush(_) => Score::RoyalFlush,
+            }
+        }
+
+        // Chance of drawing to this category.
+        pub fn probability(&self) -> Rational {
+            match self {
+                PartialScore::Pair(p)
+                | PartialScore::TwoPair(p)
+                | PartialScore::ThreeOfAKind(p)
+                | PartialScore::Straight(p)
+                | PartialScore::Flush(p)
+                | PartialScore::FullHouse(p)
+                | PartialScore::FourOfAKind(p)
+                | PartialScore::StraightFlush(p)
+                | PartialScore::RoyalFlush(p) => *p,
             }
         }
     }
@@ -513,7 +674,7 @@ pub mod tree_check {
         }
     }
 
-    struct DeckTree {
+    pub(crate) struct DeckTree {
         enumerations: i32,
         pairs: i32,
         two_pairs: i32,
@@ -542,6 +703,23 @@ pub mod tree_check {
             }
         }
 
+        // `counts` is indexed by `Score as usize`, for callers (all_deals)
+        // that tally outcomes without going through `record`.
+        pub(crate) fn from_counts(enumerations: i32, counts: [i32; 9]) -> Self {
+            DeckTree {
+                enumerations,
+                pairs: counts[Score::Pair as usize],
+                two_pairs: counts[Score::TwoPair as usize],
+                three_of_a_kinds: counts[Score::ThreeOfAKind as usize],
+                straights: counts[Score::Straight as usize],
+                flushes: counts[Score::Flush as usize],
+                full_houses: counts[Score::FullHouse as usize],
+                four_of_a_kinds: counts[Score::FourOfAKind as usize],
+                straight_flushes: counts[Score::StraightFlush as usize],
+                royal_flushes: counts[Score::RoyalFlush as usize],
+            }
+        }
+
         // Increments the one counter `score` maps to (plus enumerations)
         // directly, instead of building a 9-zero-field DeckTree just to
         // AddAssign it in -- this runs on every leaf hand.
@@ -771,12 +949,17 @@ pub mod tree_check {
     }
 
     fn score(hand: &[&Card], deck: &Deck, multiplier: i32) -> Rational {
-        const MAX_MULTIPLIER: i32 = 9999;
-        let scores = check(hand, deck);
+        expected_score(&check(hand, deck), multiplier)
+    }
+
+    // Chips expected at a stake of `multiplier`, with each payout capped as
+    // the game caps it.
+    pub fn expected_score(scores: &[PartialScore], multiplier: i32) -> Rational {
+        let paytable = Paytable::default();
         scores
-            .into_iter()
+            .iter()
             .fold(Rational::zero(), |mut sum: Rational, s| {
-                sum += std::cmp::min(s.score() * multiplier, MAX_MULTIPLIER.into());
+                sum += s.probability() * paytable.payout(s.category(), multiplier) as i128;
                 sum
             })
     }
@@ -1114,7 +1297,26 @@ pub mod parse_input {
         }
     }
 
+    #[allow(clippy::result_unit_err)]
     pub fn parse(input: &str) -> Result<FullHand, ()> {
+        let cards = parse_cards(input)?;
+
+        if cards.len() != 5 {
+            Err(())
+        } else {
+            Ok(FullHand(
+                cards[0].clone(),
+                cards[1].clone(),
+                cards[2].clone(),
+                cards[3].clone(),
+                cards[4].clone(),
+            ))
+        }
+    }
+
+    // Any number of different cards, in the same formats as `parse`.
+    #[allow(clippy::result_unit_err)]
+    pub fn parse_cards(input: &str) -> Result<Vec<Card>, ()> {
         let re = Regex::new(REGEX).unwrap();
 
         if !re.is_match(input) {
@@ -1127,31 +1329,71 @@ pub mod parse_input {
             let Ok(card) = (rank, suit).try_into() else {
                 return Err(());
             };
+            if cards.contains(&card) {
+                return Err(());
+            }
             cards.push(card);
         }
 
-        if cards.len() != 5
-            || (cards[0] == cards[1]
-                || cards[0] == cards[2]
-                || cards[0] == cards[3]
-                || cards[0] == cards[4]
-                || cards[1] == cards[2]
-                || cards[1] == cards[3]
-                || cards[1] == cards[4]
-                || cards[2] == cards[3]
-                || cards[2] == cards[4]
-                || cards[3] == cards[4])
-        {
-            Err(())
-        } else {
-            Ok(FullHand(
-                cards[0].clone(),
-                cards[1].clone(),
-                cards[2].clone(),
-                cards[3].clone(),
-                cards[4].clone(),
-            ))
+        Ok(cards)
+    }
+
+    // Card positions 1-5 to hold, as a mask (bit i for the (i+1)th card):
+    // "1 3 4", "1,3,4" or "134". Swapping every card takes an explicit "-",
+    // so a stray Enter isn't read as an answer.
+    #[allow(clippy::result_unit_err)]
+    pub fn parse_hold(input: &str) -> Result<usize, ()> {
+        let input = input.trim();
+        if input == "-" {
+            return Ok(0);
+        }
+        if !input.contains(|c: char| ('1'..='5').contains(&c)) {
+            return Err(());
+        }
+
+        let mut mask = 0;
+        for c in input.chars() {
+            match c {
+                '1'..='5' => mask |= 1 << (c as usize - '1' as usize),
+                ' ' | ',' | '.' => {}
+                _ => return Err(()),
+            }
         }
+        Ok(mask)
+    }
+
+    #[test]
+    fn test_parse_hold() {
+        assert_eq!(parse_hold("1 3 4"), Ok(0b01101));
+        assert_eq!(parse_hold("5,2"), Ok(0b10010));
+        assert_eq!(parse_hold("12345\n"), Ok(0b11111));
+        assert_eq!(parse_hold("-"), Ok(0));
+        assert_eq!(parse_hold(""), Err(()));
+        assert_eq!(parse_hold("\n"), Err(()));
+        assert_eq!(parse_hold(" , "), Err(()));
+        assert_eq!(parse_hold("6"), Err(()));
+        assert_eq!(parse_hold("1 x"), Err(()));
+    }
+
+    #[test]
+    fn test_parse_score() {
+        use crate::Score;
+
+        assert_eq!("flush".parse(), Ok(Score::Flush));
+        assert_eq!("Full-House".parse(), Ok(Score::FullHouse));
+        assert_eq!("royal_flush".parse(), Ok(Score::RoyalFlush));
+        assert_eq!("flushes".parse::<Score>(), Err(()));
+    }
+
+    #[test]
+    fn test_parse_cards() {
+        assert_eq!(parse_cards("KH 10s"), Ok(vec![Card(King, Heart), Card(Ten, Spade)]));
+        assert_eq!(parse_cards("KH KH"), Err(()));
+
+        let cards = parse_cards("AC 2D 10H JS QC KD").unwrap();
+        let text: Vec<String> = cards.iter().map(Card::to_string).collect();
+        assert_eq!(text.join(" "), "AC 2D 10H JS QC KD");
+        assert_eq!(parse_cards(&text.join(" ")), Ok(cards));
     }
 
     #[test]
@@ -0,0 +1,230 @@
+use crate::Card;
+use crate::Deck;
+use crate::FullHand;
+use crate::MAX_MULTIPLIER;
+use crate::Score;
+use crate::check;
+use crate::paytable::Paytable;
+use crate::stats::normal_cdf;
+use crate::tree_check;
+
+use std::collections::BTreeMap;
+
+use rational::Rational;
+
+// How one draw turned out against what its hold was expected to pay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Draw {
+    pub keep: usize,
+    pub score: Option<Score>,
+    pub payout: i32,
+    pub expected: Rational,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tally {
+    pub hits: u64,
+    // Summed chance of hitting it, over every hand.
+    pub expected: f64,
+}
+
+// Why a draw can't be recorded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    // Not a stake the game offers.
+    Multiplier(i32),
+    // The drawn hand has this card twice.
+    Repeated(Card),
+    // This card was neither dealt nor left in the deck to draw.
+    NotInDeck(Card),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Error::Multiplier(_) => write!(f, "Multiplier out of range"),
+            Error::Repeated(card) => write!(f, "The draw repeats {card}"),
+            Error::NotInDeck(card) => write!(f, "{card} wasn't dealt or left to draw"),
+        }
+    }
+}
+
+// Chips won against chips expected, over the hands played so far.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    pub hands: u64,
+    pub actual: i64,
+    pub expected: Rational,
+    // Of `actual`, summed over hands, which are independent.
+    pub variance: f64,
+    // By category, with `None` for hands that paid nothing.
+    pub categories: BTreeMap<Option<Score>, Tally>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            hands: 0,
+            actual: 0,
+            expected: Rational::zero(),
+            variance: 0.0,
+            categories: BTreeMap::new(),
+        }
+    }
+
+    // Scores `drawn`, the hand after the draw from `dealt` with `deck` left
+    // to draw from, at a stake of `multiplier`. The hold is whichever dealt
+    // cards are still there. Errs if `drawn` repeats a card or draws one
+    // that isn't in `deck`, since it can't have come from that draw, or if
+    // the multiplier isn't one the game offers.
+    pub fn record(
+        &mut self,
+        dealt: &FullHand,
+        deck: &Deck,
+        drawn: &FullHand,
+        multiplier: i32,
+    ) -> Result<Draw, Error> {
+        if !(1..=MAX_MULTIPLIER).contains(&multiplier) {
+            return Err(Error::Multiplier(multiplier));
+        }
+        let cards = drawn.cards();
+        if let Some(i) = (1..cards.len()).find(|&i| cards[..i].contains(&cards[i])) {
+            return Err(Error::Repeated(cards[i].clone()));
+        }
+        let dealt_cards = dealt.cards();
+        if let Some(card) = cards.iter().find(|card| !dealt_cards.contains(card) && !deck.cards.contains(card)) {
+            return Err(Error::NotInDeck((*card).clone()));
+        }
+
+        let keep: Vec<&Card> = dealt_cards
+            .into_iter()
+            .filter(|card| cards.contains(card))
+            .collect();
+        let outcomes = tree_check::check(&keep, deck);
+
+        let paytable = Paytable::default();
+        let payout = |score: Score| paytable.payout(score, multiplier);
+        let expected = tree_check::expected_score(&outcomes, multiplier);
+        let square: f64 = outcomes
+            .iter()
+            .map(|outcome| {
+                outcome.probability().decimal_value() * (payout(outcome.category()) as f64).powi(2)
+            })
+            .sum();
+        self.variance += square - expected.decimal_value().powi(2);
+
+        let mut nothing = 1.0;
+        for outcome in &outcomes {
+            let probability = outcome.probability().decimal_value();
+            self.categories.entry(Some(outcome.category())).or_default().expected += probability;
+            nothing -= probability;
+        }
+        self.categories.entry(None).or_default().expected += nothing;
+
+        let score = check::check(&cards);
+        let draw = Draw {
+            keep: dealt.mask(&keep),
+            score,
+            payout: score.map_or(0, payout),
+            expected,
+        };
+        self.categories.entry(score).or_default().hits += 1;
+        self.hands += 1;
+        self.actual += draw.payout as i64;
+        self.expected += expected;
+        Ok(draw)
+    }
+
+    // Chips won beyond what the holds were expected to pay.
+    pub fn luck(&self) -> f64 {
+        self.actual as f64 - self.expected.decimal_value()
+    }
+
+    // Share of sessions with these holds that would have done no better,
+    // by a normal approximation of the total; 0.5 is par. None before any
+    // hand that could have paid differently.
+    pub fn percentile(&self) -> Option<f64> {
+        if self.variance <= 0.0 {
+            return None;
+        }
+        Some(normal_cdf(self.luck() / self.variance.sqrt()))
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+#[test]
+fn test_session() {
+    use crate::parse_input::parse;
+
+    let dealt = parse("KH 8D 3D 4D 6D").unwrap();
+    let deck = (&dealt).into();
+    let mut session = Session::new();
+
+    // Held the flush draw and hit it.
+    let draw = session.record(&dealt, &deck, &parse("2D 8D 3D 4D 6D").unwrap(), 1).unwrap();
+    assert_eq!(draw.keep, 0b11110);
+    assert_eq!(draw.score, Some(Score::Flush));
+    assert_eq!(draw.payout, 10);
+    assert_eq!(draw.expected, Rational::new(114, 47));
+
+    // Held the King and missed.
+    let draw = session.record(&dealt, &deck, &parse("KH 9C 2S 5C JD").unwrap(), 1).unwrap();
+    assert_eq!(draw.keep, 0b00001);
+    assert_eq!(draw.payout, 0);
+
+    assert_eq!(session.hands, 2);
+    assert_eq!(session.actual, 10);
+    assert_eq!(session.categories[&Some(Score::Flush)].hits, 1);
+    assert_eq!(session.categories[&None].hits, 1);
+    let expected: f64 = session.categories.values().map(|tally| tally.expected).sum();
+    assert!((expected - 2.0).abs() < 1e-9);
+    assert!(session.percentile().unwrap() > 0.5);
+
+    let mut repeated = parse("KH 9C 2S 5C JD").unwrap();
+    repeated.1 = repeated.0.clone();
+    assert_eq!(session.record(&dealt, &deck, &repeated, 1), Err(Error::Repeated(repeated.0.clone())));
+    let drawn = parse("KH 9C 2S 5C JD").unwrap();
+    assert_eq!(session.record(&dealt, &deck, &drawn, 0), Err(Error::Multiplier(0)));
+    assert_eq!(
+        session.record(&dealt, &deck, &drawn, 2_000_000_000),
+        Err(Error::Multiplier(2_000_000_000))
+    );
+    assert_eq!(session.hands, 2);
+}
+
+#[test]
+fn test_dead() {
+    use crate::parse_input::parse;
+
+    // With 2D dead there are 8 diamonds left to flush with, out of 46 cards.
+    let dealt = parse("KH 8D 3D 4D 6D").unwrap();
+    let deck = Deck::from(&dealt).take_card(&parse("2D 9C 2S 5C JD").unwrap().0);
+    let mut session = Session::new();
+    let draw = session.record(&dealt, &deck, &parse("5D 8D 3D 4D 6D").unwrap(), 1).unwrap();
+    assert_eq!(draw.expected, Rational::new(8 * 10 + 12 * 2, 46));
+    let drawn = parse("2D 8D 3D 4D 6D").unwrap();
+    assert_eq!(session.record(&dealt, &deck, &drawn, 1), Err(Error::NotInDeck(drawn.0.clone())));
+    assert_eq!(Error::NotInDeck(drawn.0).to_string(), "2D wasn't dealt or left to draw");
+}
+
+#[test]
+fn test_capped() {
+    use crate::parse_input::parse;
+
+    // Four to a royal at x100: the royal and the straight flush both pay
+    // the 9999 cap, six flushes 1000, six straights 800 and twelve pairs
+    // 200, out of 47 draws.
+    let dealt = parse("10H JH QH KH 2H").unwrap();
+    let mut session = Session::new();
+    let draw = session.record(&dealt, &(&dealt).into(), &parse("10H JH QH KH AH").unwrap(), 100).unwrap();
+    assert_eq!(draw.payout, MAX_MULTIPLIER);
+    assert_eq!(draw.expected, Rational::new(2 * 9999 + 6 * 1000 + 6 * 800 + 12 * 200, 47));
+    let outcomes = tree_check::check(&dealt.keep(0b01111), &(&dealt).into());
+    assert_eq!(draw.expected, tree_check::expected_score(&outcomes, 100));
+    assert!(session.variance > 0.0);
+}
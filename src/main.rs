@@ -1,18 +1,100 @@
+use check::all_deals;
+use check::chart;
+use check::classify;
+#[cfg(unix)]
+use check::daemon;
+use check::drill;
+use check::fairness;
+use check::history;
+use check::hold;
+use check::luck;
+use check::monte_carlo;
+use check::multiplier;
 use check::parse_input;
+use check::paytable;
+use check::rpc;
+#[cfg(feature = "screenshot")]
+use check::screenshot;
+use check::server;
+use check::simulate;
+use check::strategy;
+use check::strategy::Strategy;
+use check::trainer;
+#[cfg(feature = "tui")]
+use check::tui;
 use check::tree_check;
+use check::watch;
 use check::Card;
+use check::FullHand;
+use itertools::Itertools;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 use rational::Rational;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("mc") => monte_carlo_check(&args[1..]),
+        Some("sim") => simulate_strategies(&args[1..]),
+        Some("strategy") => evaluate_strategy(&args[1..]),
+        Some("chart") => strategy_chart(&args[1..]),
+        Some("train") => train(&args[1..]),
+        Some("hold") => regret_check(&args[1..]),
+        Some("replay") => replay(&args[1..]),
+        Some("fairness") => fairness(&args[1..]),
+        Some("rtp") => return_to_player(&args[1..]),
+        Some("sweep") => paytable_sweep(&args[1..]),
+        Some("multipliers") => multiplier_sweep(&args[1..]),
+        Some("serve") => serve(&args[1..]),
+        Some("rpc") => json_rpc(),
+        Some("watch") => watch(&args[1..]),
+        #[cfg(unix)]
+        Some("daemon") => run_daemon(&args[1..]),
+        #[cfg(unix)]
+        Some("query") => query(&args[1..]),
+        #[cfg(feature = "tui")]
+        Some("tui") => terminal_ui(&args[1..]),
+        _ => interactive(&args),
+    }
+}
+
+// check [--buttons] [--log <file>] [--dead <cards>]
+fn interactive(args: &[String]) {
     use std::io::stdin;
 
+    #[cfg(feature = "screenshot")]
+    if args.iter().any(|arg| arg == "--image") {
+        return from_screenshot(args);
+    }
+    #[cfg(not(feature = "screenshot"))]
+    if args.iter().any(|arg| arg == "--image") {
+        println!("--image needs a build with --features screenshot");
+        return;
+    }
+
+    let buttons = args.iter().any(|arg| arg == "--buttons");
+    let log = flag_value(args, "--log").map(std::path::PathBuf::from);
+    // Cards known to be out of the deck for every hand, e.g. "5D 7C".
+    let dead = match flag_value(args, "--dead").map(parse_input::parse_cards) {
+        None => Vec::new(),
+        Some(Ok(dead)) => dead,
+        Some(Err(())) => {
+            println!("Usage: check [--buttons] [--log <file>] [--dead <cards>]");
+            return;
+        }
+    };
+
     print_usage();
     let mut hand_string = String::new();
     let mut multiplier_string = String::new();
+    let mut drawn_string = String::new();
+    let mut session = luck::Session::new();
 
     loop {
         hand_string.clear();
         multiplier_string.clear();
+        drawn_string.clear();
 
         println!("Enter hand:");
         stdin()
@@ -31,26 +113,858 @@ fn main() {
 
         let multiplier: i32 = multiplier_string.trim().parse().expect("Input not an integer");
 
-        let deck = (&full_hand).into();
+        if let Some(card) = dead.iter().find(|card| full_hand.cards().contains(card)) {
+            println!("{card} was dealt, but it's dead");
+            continue;
+        }
+
+        let mut entry = history::Entry::new(&full_hand, multiplier, &dead, 0);
+        let deck = entry.deck().unwrap();
         let ranked = tree_check::ranked_swap_values(&full_hand, &deck, multiplier);
-        print_results(&ranked, multiplier);
+        print_results(&full_hand, &ranked, multiplier, buttons);
+        entry.recommended = hold::positions(full_hand.mask(&ranked[0].0));
+
+        println!("\nEnter the hand after the draw (or leave blank to skip):");
+        stdin()
+            .read_line(&mut drawn_string)
+            .expect("Did not enter a correct hand");
+        if !drawn_string.trim().is_empty() {
+            let draw = parse_input::parse(&drawn_string)
+                .map_err(|()| "Not a hand".to_string())
+                .and_then(|drawn| {
+                    let draw = session
+                        .record(&full_hand, &deck, &drawn, multiplier)
+                        .map_err(|error| error.to_string())?;
+                    entry.draw = Some(history::cards_text(&drawn));
+                    Ok(draw)
+                });
+            match draw {
+                Ok(draw) => {
+                    entry.held = Some(hold::positions(draw.keep));
+                    entry.payout = Some(draw.payout);
+                    print_luck(&session, &draw);
+                }
+                Err(reason) => println!("{reason}; skipped"),
+            }
+        }
+
+        if let Some(log) = &log {
+            history::append(log, &entry).expect("Could not write to the log");
+        }
+    }
+}
+
+// check --image <screenshot> [multiplier] [--buttons]
+//
+// Experimental, so only built with --features screenshot: see
+// src/screenshot.rs.
+#[cfg(feature = "screenshot")]
+fn from_screenshot(args: &[String]) {
+    let Some(path) = args.iter().skip_while(|arg| *arg != "--image").nth(1) else {
+        println!("Usage: check --image <screenshot> [multiplier] [--buttons]");
+        return;
+    };
+    let buttons = args.iter().any(|arg| arg == "--buttons");
+    let multiplier: i32 = positional(args, &["--image"])
+        .into_iter()
+        .find(|arg| *arg != "--buttons")
+        .map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+
+    let recognised = match screenshot::open(std::path::Path::new(path)) {
+        Ok(recognised) => recognised,
+        Err(error) => {
+            eprintln!("{error}");
+            return;
+        }
+    };
+    let full_hand = recognised.full_hand;
+    println!("Reading screenshots is experimental, so check these against the screen:\n");
+    for (card, confidence) in full_hand.cards().iter().zip(recognised.confidence) {
+        let doubt = if confidence < 0.5 { "  <- check this one" } else { "" };
+        println!("{card:>3}  {:.0}%{doubt}", confidence * 100.0);
+    }
+    println!();
+
+    let deck = (&full_hand).into();
+    let ranked = tree_check::ranked_swap_values(&full_hand, &deck, multiplier);
+    print_results(&full_hand, &ranked, multiplier, buttons);
+}
+
+// The argument after `flag`, if it's there.
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .map(|i| args.get(i + 1).unwrap_or_else(|| panic!("{flag} needs a value")).as_str())
+}
+
+// The arguments that are neither one of `flags` nor the value after it.
+fn positional<'a>(args: &'a [String], flags: &[&str]) -> Vec<&'a String> {
+    let is_flag = |arg: &String| flags.contains(&arg.as_str());
+    args.iter()
+        .enumerate()
+        .filter(|(i, arg)| !is_flag(arg) && (*i == 0 || !is_flag(&args[i - 1])))
+        .map(|(_, arg)| arg)
+        .collect()
+}
+
+// check replay <file> [--since YYYY-MM-DD] [--until YYYY-MM-DD] [--pattern <hold>]
+fn replay(args: &[String]) {
+    let date = |flag| {
+        flag_value(args, flag).map(|date| {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").expect("Dates are YYYY-MM-DD")
+        })
+    };
+    let Some(path) = args.first() else {
+        println!("Usage: check replay <file> [--since YYYY-MM-DD] [--until YYYY-MM-DD] [--pattern <hold>]");
+        return;
+    };
+    let filter = history::Filter {
+        since: date("--since"),
+        until: date("--until"),
+        pattern: flag_value(args, "--pattern").map(str::to_string),
+    };
+
+    let entries = history::load(std::path::Path::new(path)).expect("Could not read the log");
+    let replay = history::replay(&entries, &filter).expect("The log has a hand that doesn't parse");
+    for deviation in &replay.deviations {
+        let entry = &entries[deviation.entry];
+        let grade = &deviation.grade;
+        println!(
+            "{}  {}  x{}  held {} ({} of 32), best {} ({}): -{:.2}, -{:.2} in all",
+            entry.time.format("%Y-%m-%d %H:%M"),
+            entry.deal,
+            entry.multiplier,
+            hold::strip(grade.chosen),
+            grade.rank,
+            hold::strip(grade.best),
+            grade.best_class,
+            grade.ev_lost().decimal_value(),
+            deviation.cumulative_ev_lost.decimal_value(),
+        );
+    }
+    println!(
+        "\n{} of {} hands played off the best hold, {:.2} chips of EV lost",
+        replay.deviations.len(),
+        replay.hands,
+        replay.ev_lost.decimal_value()
+    );
+}
+
+// check rtp [multiplier] [--paytable <pair,two pair,...,royal flush>]
+fn return_to_player(args: &[String]) {
+    let paytable: paytable::Paytable = flag_value(args, "--paytable")
+        .map_or_else(Default::default, |pays| {
+            pays.parse().expect("A paytable is nine pays, pair to royal flush")
+        });
+    let multiplier: i32 = positional(args, &["--paytable"])
+        .first()
+        .map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+
+    let result = paytable::rtp(
+        &all_deals::Table::new(),
+        &all_deals::deal_classes(),
+        &paytable,
+        multiplier,
+    );
+    println!("Paytable {paytable}, multiplier {multiplier}, optimal play:\n");
+    println!("{:<16} {:>5} {:>11} {:>12}", "", "pays", "frequency", "RTP share");
+    for share in &result.shares {
+        println!(
+            "{:<16} {:>5} {:>10.5}% {:>11.5}%",
+            share.category.map_or("nothing".to_string(), |score| score.to_string()),
+            share.category.map_or(0, |score| paytable.pay(score)),
+            share.frequency.decimal_value() * 100.0,
+            share.contribution.decimal_value() * 100.0,
+        );
+    }
+    println!("\nReturn to player: {:.5}% ({})", result.rtp.decimal_value() * 100.0, result.rtp);
+}
+
+// check sweep <category> <pay,pay,...> [multiplier] [--paytable <pays>]
+// check sweep <category> --target <RTP %> [multiplier] [--paytable <pays>]
+fn paytable_sweep(args: &[String]) {
+    let usage = "Usage: check sweep <category> (<pay,pay,...> | --target <RTP %>) [multiplier] [--paytable <pays>]";
+    let positional = positional(args, &["--paytable", "--target"]);
+    let Some(Ok(category)) = positional.first().map(|name| name.parse::<check::Score>()) else {
+        println!("{usage}");
+        return;
+    };
+    let target = flag_value(args, "--target").map(|rtp| rtp.parse::<f64>().expect("Target not a number"));
+    let (pays, multiplier) = match target {
+        Some(_) => (None, positional.get(1)),
+        None => (positional.get(1), positional.get(2)),
+    };
+    let multiplier: i32 = multiplier.map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+    let base: paytable::Paytable = flag_value(args, "--paytable")
+        .map_or_else(Default::default, |pays| {
+            pays.parse().expect("A paytable is nine pays, pair to royal flush")
+        });
+
+    let table = all_deals::Table::new();
+    let classes = all_deals::deal_classes();
+
+    if let Some(target) = target {
+        // To a millionth of a percent, as an exact fraction.
+        let target = Rational::new((target * 1e6).round() as i128, 100_000_000);
+        match paytable::solve_pay(&table, &classes, &base, category, target, multiplier) {
+            Some((pay, result)) => println!(
+                "A {category} has to pay {pay} for an RTP of {:.5}% ({})",
+                result.rtp.decimal_value() * 100.0,
+                result.paytable
+            ),
+            None => println!("No {category} pay up to the cap gets an RTP that high"),
+        }
+        return;
+    }
+
+    let Some(pays) = pays else {
+        println!("{usage}");
+        return;
+    };
+    let variants: Vec<paytable::Paytable> = pays
+        .split(',')
+        .map(|pay| {
+            let mut paytable = base;
+            paytable.pays[category as usize] = pay.trim().parse().expect("Pay not an integer");
+            paytable
+        })
+        .collect();
+
+    println!("Paying a {category} (base paytable {base}, multiplier {multiplier}):");
+    for variant in paytable::sweep(&table, &classes, &base, &variants, multiplier) {
+        println!(
+            "\n{:>6}: RTP {:.5}%, best hold changes on {:.3}% of deals",
+            variant.rtp.paytable.pay(category),
+            variant.rtp.rtp.decimal_value() * 100.0,
+            variant.flipped as f64 * 100.0 / variant.deals as f64,
+        );
+        for flip in &variant.flips {
+            println!(
+                "        {}  {} ({}) -> {} ({})",
+                history::cards_text(&flip.full_hand),
+                hold::strip(flip.before),
+                classify::classify(&flip.full_hand, &flip.full_hand.keep(flip.before)),
+                hold::strip(flip.after),
+                classify::classify(&flip.full_hand, &flip.full_hand.keep(flip.after)),
+            );
+        }
+    }
+}
+
+// check multipliers ["<hand>"] [--paytable <pays>] [--csv]
+fn multiplier_sweep(args: &[String]) {
+    let paytable: paytable::Paytable = flag_value(args, "--paytable")
+        .map_or_else(Default::default, |pays| {
+            pays.parse().expect("A paytable is nine pays, pair to royal flush")
+        });
+    let rest: Vec<&String> = positional(args, &["--paytable"])
+        .into_iter()
+        .filter(|arg| arg.as_str() != "--csv")
+        .collect();
+
+    let Some(hand) = rest.first() else {
+        let result = multiplier::thresholds(
+            &all_deals::Table::new(),
+            &all_deals::deal_classes(),
+            &paytable,
+        );
+        let percent = |deals: u64| deals as f64 / result.deals as f64 * 100.0;
+        println!(
+            "Paytable {paytable}: {} of {} deals ({:.4}%) change best hold between x1 and x{}\n",
+            result.changing,
+            result.deals,
+            percent(result.changing),
+            multiplier::MAX
+        );
+        println!("{:>6} {:>9} {:>9}  e.g.", "from", "deals", "share");
+        for (m, deals) in &result.at {
+            let (full_hand, segments) = &result.examples[m];
+            let i = segments.iter().position(|segment| segment.from == *m).unwrap();
+            println!(
+                "{:>6} {:>9} {:>8.4}%  {}: {} -> {}",
+                format!("x{m}"),
+                deals,
+                percent(*deals),
+                history::cards_text(full_hand),
+                hold::strip(segments[i - 1].best),
+                hold::strip(segments[i].best)
+            );
+        }
+        return;
+    };
+
+    let Ok(full_hand) = parse_input::parse(hand) else {
+        println!("Usage: check multipliers [\"<hand>\"] [--paytable <pays>] [--csv]");
+        return;
+    };
+    let curves = multiplier::curves(&full_hand, &(&full_hand).into());
+    let segments = multiplier::segments(&curves, &paytable);
+    let per_chip = |mask: usize, m: i32| curves[mask].expected(&paytable, m).decimal_value() / m as f64;
+
+    // Every hold best somewhere, then the runners-up at x1.
+    let mut top: Vec<usize> = segments.iter().map(|segment| segment.best).collect();
+    let mut at_one: Vec<usize> = (0..curves.len()).collect();
+    at_one.sort_by(|&a, &b| curves[b].expected(&paytable, 1).cmp(&curves[a].expected(&paytable, 1)));
+    top.extend(at_one);
+    let top: Vec<usize> = top.into_iter().unique().take(segments.len().max(3)).collect();
+
+    if args.iter().any(|arg| arg == "--csv") {
+        let masks: Vec<String> = top.iter().map(|&mask| format!("\"{}\"", hold::strip(mask))).collect();
+        println!("multiplier,{}", masks.join(","));
+        for m in 1..=multiplier::MAX {
+            let values: Vec<String> = top.iter().map(|&mask| format!("{:.6}", per_chip(mask, m))).collect();
+            println!("{m},{}", values.join(","));
+        }
+        return;
+    }
+
+    let describe = |mask: usize| {
+        let keep = full_hand.keep(mask);
+        format!("{:?} ({})", keep, classify::classify(&full_hand, &keep))
+    };
+    println!("Paytable {paytable}:\n");
+    for (segment, next) in segments.iter().zip(segments.iter().skip(1).map(Some).chain([None])) {
+        match next {
+            Some(next) => println!(
+                "{} is best up to x{}, then switch to {}",
+                describe(segment.best),
+                segment.to,
+                describe(next.best)
+            ),
+            None if segment.from == 1 => println!("{} is best at every multiplier", describe(segment.best)),
+            None => println!("{} is best from x{} on", describe(segment.best), segment.from),
+        }
+    }
+
+    // Where the lines bend or cross.
+    let mut points: Vec<i32> = segments.iter().flat_map(|segment| [segment.from, segment.to]).collect();
+    points.extend(multiplier::caps(&paytable));
+    let points: Vec<i32> = points.into_iter().sorted().dedup().collect();
+
+    println!("\nExpected score per chip:\n");
+    print!("{:>6}", "");
+    for &mask in &top {
+        print!("  {:>11}", hold::strip(mask).trim_start_matches("HOLD: "));
+    }
+    println!();
+    for m in points {
+        print!("{:>6}", format!("x{m}"));
+        for &mask in &top {
+            let best = segments.iter().any(|segment| segment.best == mask && (segment.from..=segment.to).contains(&m));
+            print!("  {:>10.4}{}", per_chip(mask, m), if best { "*" } else { " " });
+        }
+        println!();
+    }
+}
+
+// check serve [--host <address>] [--port <port>] [--workers <threads>] [--queue <waiting requests>]
+fn serve(args: &[String]) {
+    let defaults = server::Config::default();
+    let config = server::Config {
+        host: flag_value(args, "--host").map_or(defaults.host, str::to_string),
+        port: flag_value(args, "--port").map_or(defaults.port, |port| port.parse().expect("Port not a number")),
+        workers: flag_value(args, "--workers")
+            .map_or(defaults.workers, |workers| workers.parse().expect("Workers not a number")),
+        queue: flag_value(args, "--queue").map_or(defaults.queue, |queue| queue.parse().expect("Queue not a number")),
+    };
+    let server = match server::Server::bind(&config) {
+        Ok(server) => server,
+        Err(error) => {
+            println!("Couldn't listen on port {}: {error}", config.port);
+            return;
+        }
+    };
+    println!(
+        "Open http://{} in a browser ({} workers, {} requests queued at most)",
+        server.addr(),
+        config.workers,
+        config.queue
+    );
+    if !server.addr().ip().is_loopback() {
+        println!("Other machines on the network get the page and the analysis, but not /rtp");
+    }
+    server.run(server::Api::default());
+}
+
+// check rpc
+fn json_rpc() {
+    if let Err(error) = rpc::run(std::io::stdin().lock(), std::io::stdout().lock()) {
+        eprintln!("{error}");
     }
 }
 
-fn print_results(ranked: &[(Vec<&Card>, Rational)], multiplier: i32) {
+// check watch <file or FIFO> [multiplier] [--json]
+fn watch(args: &[String]) {
+    let as_json = args.iter().any(|arg| arg == "--json");
+    let mut rest = args.iter().filter(|arg| *arg != "--json");
+    let Some(path) = rest.next() else {
+        println!("Usage: check watch <file or FIFO> [multiplier] [--json]");
+        return;
+    };
+    let multiplier = match rest.next().map_or(Ok(1), |m| m.parse::<i32>()) {
+        Ok(m) if (1..=multiplier::MAX).contains(&m) => m,
+        _ => {
+            println!("The multiplier is 1-{}", multiplier::MAX);
+            return;
+        }
+    };
+
+    let follow = match watch::Follow::open(std::path::Path::new(path)) {
+        Ok(follow) => follow,
+        Err(error) => {
+            eprintln!("Couldn't open {path}: {error}");
+            return;
+        }
+    };
+    if let Err(error) = watch::run(follow, multiplier, as_json, std::io::stdout().lock()) {
+        eprintln!("{error}");
+    }
+}
+
+// check daemon [--socket <path>]
+#[cfg(unix)]
+fn run_daemon(args: &[String]) {
+    let path = flag_value(args, "--socket").map_or_else(daemon::default_socket, std::path::PathBuf::from);
+    let listener = match daemon::bind(&path) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("{}: {error}", path.display());
+            return;
+        }
+    };
+    println!("Building the deal table...");
+    let daemon = std::sync::Arc::new(daemon::Daemon::new());
+    println!("Listening on {}", path.display());
+    daemon.serve(listener);
+}
+
+// check query <hand> [multiplier] [--dead <cards>] [--top <n>] [--json] [--socket <path>]
+#[cfg(unix)]
+fn query(args: &[String]) {
+    let as_json = args.iter().any(|arg| arg == "--json");
+    let mut rest = positional(args, &["--dead", "--top", "--socket"])
+        .into_iter()
+        .filter(|arg| *arg != "--json");
+    let Some(hand) = rest.next() else {
+        println!("Usage: check query <hand> [multiplier] [--dead <cards>] [--top <n>] [--json] [--socket <path>]");
+        return;
+    };
+    let multiplier: i32 = rest.next().map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+    let top: usize = flag_value(args, "--top").map_or(3, |top| top.parse().expect("Top not an integer"));
+    let dead: Vec<&str> = flag_value(args, "--dead").into_iter().collect();
+    let path = flag_value(args, "--socket").map_or_else(daemon::default_socket, std::path::PathBuf::from);
+
+    let request = serde_json::json!({ "hand": hand, "multiplier": multiplier, "dead": dead, "top": top });
+    match daemon::query(&path, &request) {
+        Ok(answer) if as_json => println!("{answer}"),
+        Ok(answer) => match answer["error"].as_str() {
+            Some(error) => eprintln!("{error}"),
+            None => println!("{}", watch::summary(&answer)),
+        },
+        Err(error) => eprintln!("{}: {error} (is `check daemon` running?)", path.display()),
+    }
+}
+
+// check tui [multiplier]
+#[cfg(feature = "tui")]
+fn terminal_ui(args: &[String]) {
+    let multiplier: i32 = args.first().map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+    if let Err(error) = tui::run(multiplier) {
+        eprintln!("{error}");
+    }
+}
+
+// check fairness <file>
+fn fairness(args: &[String]) {
+    let Some(path) = args.first() else {
+        println!("Usage: check fairness <file>");
+        return;
+    };
+
+    let entries = history::load(std::path::Path::new(path)).expect("Could not read the log");
+    let tests = fairness::analyse(&entries).expect("The log has a hand that doesn't parse");
+    if tests.is_empty() {
+        println!("Not enough hands in the log to test anything yet");
+    }
+    for test in tests {
+        let statistic = match test.dof {
+            Some(dof) => format!("chi-square {:.2} on {dof} dof", test.statistic),
+            None => format!("z {:+.2}", test.statistic),
+        };
+        println!("{:<25} {:>6} samples  {:<28} p = {:.4}", test.name, test.samples, statistic, test.p_value);
+    }
+}
+
+fn print_luck(session: &luck::Session, draw: &luck::Draw) {
+    println!(
+        "{}: paid {}, against {:.2} expected for holding {}",
+        draw.score.map_or("nothing".to_string(), |score| score.to_string()),
+        draw.payout,
+        draw.expected.decimal_value(),
+        hold::strip(draw.keep),
+    );
+
+    println!("\nSession, {} hands:", session.hands);
+    for (category, tally) in session.categories.iter().rev() {
+        println!(
+            "  {:<16} {:>4} hit  {:>8.2} expected",
+            category.map_or("nothing".to_string(), |score| score.to_string()),
+            tally.hits,
+            tally.expected,
+        );
+    }
+    print!(
+        "  {} chips won, {:.2} expected: {:+.2}",
+        session.actual,
+        session.expected.decimal_value(),
+        session.luck()
+    );
+    match session.percentile() {
+        Some(percentile) => println!(" (luckier than {:.0}% of sessions)", percentile * 100.0),
+        None => println!(),
+    }
+}
+
+// check mc "<hand>" [multiplier] [trials] [seed]
+fn monte_carlo_check(args: &[String]) {
+    const Z_95: f64 = 1.96;
+
+    let Some(Ok(full_hand)) = args.first().map(|hand| parse_input::parse(hand)) else {
+        println!("Usage: check mc \"<hand>\" [multiplier] [trials] [seed]");
+        return;
+    };
+    let multiplier: i32 = args.get(1).map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+    let trials: u64 = args.get(2).map_or(1_000_000, |t| t.parse().expect("Trials not an integer"));
+    let seed: u64 = args.get(3).map_or(0, |s| s.parse().expect("Seed not an integer"));
+    if !(1..=monte_carlo::UNCAPPED).contains(&multiplier) {
+        println!("Trials aren't capped, so the multiplier is 1-{}", monte_carlo::UNCAPPED);
+        return;
+    }
+
+    let deck = (&full_hand).into();
+    let mut disagreements = 0;
+    for comparison in monte_carlo::cross_check(&full_hand, &deck, multiplier, trials, seed) {
+        let (low, high) = comparison.estimate.confidence_interval(Z_95);
+        let flag = if comparison.agrees(Z_95) {
+            ""
+        } else {
+            disagreements += 1;
+            "  <-- outside 95% interval"
+        };
+        println!(
+            "{:<25} exact {:>9.4}  estimate {:>9.4} [{:.4}, {:.4}]{flag}",
+            format!("{:?}", comparison.keep),
+            comparison.exact_f64(),
+            comparison.estimate.mean,
+            low,
+            high,
+        );
+    }
+    // 95% intervals over 32 holds should miss ~1.6 of them by chance alone.
+    println!("\n{disagreements}/32 holds outside their 95% interval ({trials} trials each)");
+}
+
+// check sim [rounds] [multiplier] [seed]
+fn simulate_strategies(args: &[String]) {
+    const CHECKPOINTS: usize = 10;
+
+    let rounds: u64 = args.first().map_or(1000, |r| r.parse().expect("Rounds not an integer"));
+    let multiplier: i32 = args.get(1).map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+    let seed: u64 = args.get(2).map_or(0, |s| s.parse().expect("Seed not an integer"));
+
+    let strategies: [Box<dyn Strategy>; 4] = [
+        Box::new(strategy::Optimal),
+        Box::new(strategy::RuleBased::default()),
+        Box::new(strategy::KeepPairs),
+        Box::new(strategy::Random::new(seed)),
+    ];
+    for mut strategy in strategies {
+        let report = simulate::simulate(strategy.as_mut(), rounds, multiplier, seed);
+        println!("{}:", strategy.name());
+        println!("  return to player: {:.2}%", report.return_to_player() * 100.0);
+        println!("  std dev per round: {:.2}", report.std_dev());
+        let step = std::cmp::max(report.trajectory.len() / CHECKPOINTS, 1);
+        let checkpoints: Vec<String> = report
+            .trajectory
+            .iter()
+            .skip(step - 1)
+            .step_by(step)
+            .map(i64::to_string)
+            .collect();
+        println!("  bankroll every {step} rounds: {}", checkpoints.join(" "));
+    }
+}
+
+// check strategy [multiplier]
+fn evaluate_strategy(args: &[String]) {
+    let multiplier: i32 = args.first().map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+
+    let mut rules = strategy::RuleBased::default();
+    println!("Rules, first match wins:");
+    for (i, rule) in rules.rules.iter().enumerate() {
+        println!("{:>4}. {rule}", i + 1);
+    }
+
+    let evaluation = strategy::evaluate(&mut rules, &all_deals::Table::new(), multiplier);
+    let print_loss = |label: &str, loss: &strategy::Loss| {
+        println!(
+            "{label:<25} {:>9} deals  {:>6.2}% mistakes  {:.5} EV lost per hand",
+            loss.deals,
+            loss.mistakes as f64 * 100.0 / loss.deals as f64,
+            loss.mean().decimal_value() / multiplier as f64,
+        );
+    };
+
+    println!("\nBy best hold:");
+    for (class, loss) in &evaluation.by_class {
+        let label = class.map_or("other".to_string(), |pattern| pattern.to_string());
+        print_loss(&label, loss);
+    }
+    println!();
+    print_loss("all deals", &evaluation.total);
+}
+
+// check chart [multiplier] [--html]
+fn strategy_chart(args: &[String]) {
+    let html = args.iter().any(|arg| arg == "--html");
+    let multiplier: i32 = args
+        .iter()
+        .find(|arg| *arg != "--html")
+        .map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+
+    let deals = chart::solve(&all_deals::Table::new(), multiplier);
+    let chart = chart::learn(&deals, multiplier);
+    if html {
+        print!("{}", chart.to_html());
+    } else {
+        print!("{}", chart.to_markdown());
+    }
+}
+
+// check train [multiplier] [seed] [--drill <file>]
+fn train(args: &[String]) {
+    use std::io::stdin;
+
+    // Missed deals, and deals like them, come back from the drill file
+    // when they're due.
+    let drill_path = flag_value(args, "--drill").map(std::path::PathBuf::from);
+    let mut drill = drill_path
+        .as_ref()
+        .map(|path| drill::Drill::load(path).expect("Could not read drill file"));
+    let mut positional = positional(args, &["--drill"]).into_iter();
+
+    let multiplier: i32 = positional.next().map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+    let seed: u64 = positional.next().map_or_else(rand::random, |s| s.parse().expect("Seed not an integer"));
+
+    println!("Enter the positions of the cards to hold, e.g. \"1 3 4\", \"-\" to swap all five, \"q\" to stop.");
+    let mut trainer = trainer::Trainer::new(multiplier, seed);
+    let mut rng = StdRng::seed_from_u64(seed.wrapping_add(1));
+    let mut answer = String::new();
+
+    loop {
+        let due = drill.as_ref().and_then(drill::Drill::due).cloned();
+        let (full_hand, note) = match due.as_ref().map(|item| (&item.subject, item.lapses)) {
+            Some((drill::Subject::Hand(hand), lapses)) => {
+                (drill::disguise(hand, &mut rng), format!("  (again: missed {lapses}x)"))
+            }
+            Some((drill::Subject::Pattern(pattern), lapses)) => {
+                match drill::deal_like(*pattern, multiplier, &mut rng) {
+                    Some(full_hand) => (full_hand, format!("  ({pattern} again: missed {lapses}x)")),
+                    None => {
+                        // Don't let it hold up every hand after this one.
+                        if let Some(drill) = drill.as_mut() {
+                            drill.postpone(&drill::Subject::Pattern(*pattern));
+                        }
+                        (trainer.deal(), String::new())
+                    }
+                }
+            }
+            None => (trainer.deal(), String::new()),
+        };
+        let cards: Vec<String> = full_hand
+            .cards()
+            .iter()
+            .enumerate()
+            .map(|(i, card)| format!("{}:{card:?}", i + 1))
+            .collect();
+        println!("\n{}{note}", cards.join("  "));
+
+        let mask = loop {
+            answer.clear();
+            if stdin().read_line(&mut answer).expect("Could not read answer") == 0 || answer.trim() == "q" {
+                print_record(&trainer.record, multiplier);
+                return;
+            }
+            match parse_input::parse_hold(&answer) {
+                Ok(mask) => break mask,
+                Err(()) => println!("Positions 1 to 5, e.g. \"1 3 4\", or \"-\" to swap all five"),
+            }
+        };
+
+        let grade = trainer.answer(&full_hand, mask);
+        if let (Some(drill), Some(path)) = (drill.as_mut(), drill_path.as_ref()) {
+            drill.record(&full_hand, &grade, multiplier);
+            drill.save(path).expect("Could not save drill file");
+        }
+        let as_f64 = |score: Rational| score.decimal_value() / multiplier as f64;
+        if grade.correct() {
+            println!("Correct: {:.4}", as_f64(grade.chosen_value));
+        } else {
+            println!(
+                "Best: {:?} ({}) {:.4}; yours ranks {} of 32 at {:.4}, losing {:.4}",
+                full_hand.keep(grade.best),
+                grade.best_class,
+                as_f64(grade.best_value),
+                grade.rank,
+                as_f64(grade.chosen_value),
+                as_f64(grade.ev_lost()),
+            );
+        }
+        let total = &trainer.record.total;
+        println!(
+            "{}/{} correct, {:.4} lost",
+            total.deals - total.mistakes,
+            total.deals,
+            as_f64(total.ev_loss)
+        );
+    }
+}
+
+// check hold "<positions>" "<hand>" [multiplier]
+fn regret_check(args: &[String]) {
+    let usage = "Usage: check hold \"2 3 5\" \"<hand>\" [multiplier]";
+    let Some(Ok(mask)) = args.first().map(|hold| parse_input::parse_hold(hold)) else {
+        println!("{usage}");
+        return;
+    };
+    let Some(Ok(full_hand)) = args.get(1).map(|hand| parse_input::parse(hand)) else {
+        println!("{usage}");
+        return;
+    };
+    let multiplier: i32 = args.get(2).map_or(1, |m| m.parse().expect("Multiplier not an integer"));
+
+    let deck = (&full_hand).into();
+    let keep = full_hand.keep(mask);
+    let regret = trainer::regret(&full_hand, &deck, multiplier, &keep);
+    let grade = &regret.grade;
+    let as_f64 = |score: Rational| score.decimal_value() / multiplier as f64;
+
+    println!(
+        "Held {:?} ({}): {:.4}, ranked {} of 32",
+        keep,
+        classify::classify(&full_hand, &keep),
+        as_f64(grade.chosen_value),
+        grade.rank
+    );
+    if grade.correct() {
+        println!("That's the best hold.");
+    } else {
+        let best = full_hand.keep(grade.best);
+        println!(
+            "Best {:?} ({}): {:.4}, {:.4} more",
+            best,
+            grade.best_class,
+            as_f64(grade.best_value),
+            as_f64(grade.ev_lost())
+        );
+    }
+
+    let probability = |outcomes: &[tree_check::PartialScore], category| {
+        outcomes
+            .iter()
+            .find(|outcome| outcome.category() == category)
+            .map_or(0.0, |outcome| outcome.probability().decimal_value())
+    };
+    let nothing = |outcomes: &[tree_check::PartialScore]| {
+        1.0 - outcomes
+            .iter()
+            .map(|outcome| outcome.probability().decimal_value())
+            .sum::<f64>()
+    };
+    println!("\n{:<16} {:>9} {:>9}", "", "held", "best");
+    let mut categories: Vec<check::Score> = regret
+        .chosen
+        .iter()
+        .chain(&regret.best)
+        .map(tree_check::PartialScore::category)
+        .collect();
+    categories.sort_by(|a, b| b.cmp(a));
+    categories.dedup();
+    for category in categories {
+        println!(
+            "{:<16} {:>8.4}% {:>8.4}%",
+            category.to_string(),
+            probability(&regret.chosen, category) * 100.0,
+            probability(&regret.best, category) * 100.0
+        );
+    }
+    println!(
+        "{:<16} {:>8.4}% {:>8.4}%",
+        "nothing",
+        nothing(&regret.chosen) * 100.0,
+        nothing(&regret.best) * 100.0
+    );
+}
+
+fn print_record(record: &trainer::Record, multiplier: i32) {
+    let print_loss = |label: &str, loss: &strategy::Loss| {
+        println!(
+            "{label:<35} {:>4}/{:<4} correct  {:.4} EV lost per hand",
+            loss.deals - loss.mistakes,
+            loss.deals,
+            loss.mean().decimal_value() / multiplier as f64,
+        );
+    };
+
+    if record.total.deals == 0 {
+        return;
+    }
+    println!("\nBy best hold:");
+    for (pattern, loss) in &record.by_pattern {
+        print_loss(&pattern.to_string(), loss);
+    }
+    println!();
+    print_loss("all hands", &record.total);
+}
+
+fn print_results(
+    full_hand: &FullHand,
+    ranked: &[(Vec<&Card>, Rational)],
+    multiplier: i32,
+    buttons: bool,
+) {
     let as_f32 = |score: &Rational| score.numerator() as f32 / score.denominator() as f32;
+    let slots = |keep: &[&Card]| {
+        let mask = full_hand.mask(keep);
+        let positions: Vec<String> = hold::positions(mask).iter().map(usize::to_string).collect();
+        format!("{}  ({})", hold::strip(mask), positions.join(" "))
+    };
 
     for (label, (keep, score)) in ["1st", "2nd", "3rd"].iter().zip(ranked) {
-        println!("{label}: {:?} : {:.2}", keep, as_f32(score) / multiplier as f32);
+        println!(
+            "{label}: {:?} : {:.2}  keep: {}",
+            keep,
+            as_f32(score) / multiplier as f32,
+            classify::classify(full_hand, keep)
+        );
+        println!("     {}", slots(keep));
     }
 
     let (keep, score) = &ranked[0];
-    println!("\n\n    Best hand to keep: {:?}", keep);
+    println!("\n\n    Best hand to keep: {:?} ({})", keep, classify::classify(full_hand, keep));
+    println!("      {}", slots(keep));
     println!(
         "      it has an expected score of {:.2} ({:.1})",
         as_f32(score) / multiplier as f32,
         as_f32(score)
     );
+    if buttons {
+        let presses: Vec<String> = hold::buttons(full_hand.mask(keep))
+            .iter()
+            .map(hold::Button::to_string)
+            .collect();
+        println!("      buttons: {}", presses.join(", "));
+    }
 }
 
 fn print_usage() {
@@ -0,0 +1,199 @@
+use crate::Card;
+use crate::Deck;
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::MAX_MULTIPLIER;
+use crate::ROYAL_FLUSH_SCORE;
+use crate::check;
+use crate::par::*;
+use crate::tree_check;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rational::Rational;
+
+// The highest multiplier at which even a royal flush pays under the cap.
+pub const UNCAPPED: i32 = MAX_MULTIPLIER / ROYAL_FLUSH_SCORE as i32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub trials: u64,
+    pub mean: f64,
+    pub std_error: f64,
+}
+
+impl Estimate {
+    // Normal-approximation interval, mean ± z standard errors. z = 1.96 is
+    // the usual 95% interval; the payout distribution is heavily skewed by
+    // the rare big hands, so this is only trustworthy for large `trials`.
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        (self.mean - z * self.std_error, self.mean + z * self.std_error)
+    }
+
+    pub fn contains(&self, value: f64, z: f64) -> bool {
+        let (low, high) = self.confidence_interval(z);
+        low <= value && value <= high
+    }
+}
+
+// Estimates the expected score of keeping `hand` by drawing the missing
+// cards from `deck` at random `trials` times. Each trial is scored with
+// the same `check::check` the exact enumerator uses, but none of its
+// combination bookkeeping, so the two make a useful independent cross-check.
+//
+// The per-trial payout is `score * multiplier` uncapped, which only agrees
+// with tree_check's capped expected shares while no payout at all can reach
+// the cap: up to x`UNCAPPED`.
+pub fn estimate<R: rand::Rng + ?Sized>(
+    hand: &[&Card],
+    deck: &Deck,
+    multiplier: i32,
+    trials: u64,
+    rng: &mut R,
+) -> Estimate {
+    let swaps = HAND_SIZE - hand.len();
+    assert!(swaps <= deck.size());
+    assert!(trials > 1);
+    assert!((1..=UNCAPPED).contains(&multiplier));
+
+    // Welford's running mean/variance, so millions of trials don't lose
+    // precision summing squares of large payouts.
+    let mut mean = 0.0;
+    let mut m2 = 0.0;
+    let mut full = [hand.first().copied().unwrap_or(&deck.cards[0]); HAND_SIZE];
+    full[..hand.len()].copy_from_slice(hand);
+
+    for n in 1..=trials {
+        for (slot, card) in full[hand.len()..].iter_mut().zip(deck.sample(swaps, rng)) {
+            *slot = card;
+        }
+        let payout = check::check(&full).map_or(0, u16::from) as f64 * multiplier as f64;
+
+        let delta = payout - mean;
+        mean += delta / n as f64;
+        m2 += delta * (payout - mean);
+    }
+
+    let variance = m2 / (trials - 1) as f64;
+    Estimate {
+        trials,
+        mean,
+        std_error: (variance / trials as f64).sqrt(),
+    }
+}
+
+pub struct Comparison<'a> {
+    pub keep: Vec<&'a Card>,
+    pub exact: Rational,
+    pub estimate: Estimate,
+}
+
+impl Comparison<'_> {
+    pub fn exact_f64(&self) -> f64 {
+        self.exact.numerator() as f64 / self.exact.denominator() as f64
+    }
+
+    pub fn agrees(&self, z: f64) -> bool {
+        self.estimate.contains(self.exact_f64(), z)
+    }
+}
+
+// Runs `estimate` for every one of the 32 holds, next to the exact value
+// from `tree_check::ranked_swap_values` (and in the same best-first order).
+// Each hold gets its own RNG derived from `seed`, so the results don't
+// depend on how rayon happens to schedule the holds. `multiplier` is at most
+// `UNCAPPED`, as for `estimate`.
+pub fn cross_check<'a>(
+    full_hand: &'a FullHand,
+    deck: &Deck,
+    multiplier: i32,
+    trials: u64,
+    seed: u64,
+) -> Vec<Comparison<'a>> {
+    tree_check::ranked_swap_values(full_hand, deck, multiplier)
+        .into_par_iter()
+        .enumerate()
+        .map(|(i, (keep, exact))| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+            let estimate = estimate(&keep, deck, multiplier, trials, &mut rng);
+            Comparison {
+                keep,
+                exact,
+                estimate,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_estimate_is_seeded() {
+    use crate::Rank::*;
+    use crate::Suit::*;
+
+    let hand = vec![&Card(Ace, Heart), &Card(Ace, Diamond)];
+    let deck = Deck::new();
+
+    let a = estimate(&hand, &deck, 1, 1000, &mut StdRng::seed_from_u64(7));
+    let b = estimate(&hand, &deck, 1, 1000, &mut StdRng::seed_from_u64(7));
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_estimate_matches_tree_check() {
+    use crate::Rank::*;
+    use crate::Suit::*;
+    use crate::tree_check::PartialScore;
+
+    let hand = vec![&Card(Ace, Heart), &Card(Ace, Diamond), &Card(Three, Heart)];
+    let deck = vec![Card(Five, Diamond), Card(Three, Spade), Card(Ace, Spade)].into();
+
+    // Full house, three of a kind and two pair, 1/3 each (see
+    // tree_check::test_two_pair): (15 + 4 + 3) / 3.
+    assert_eq!(
+        tree_check::check(&hand, &deck),
+        vec![
+            PartialScore::FullHouse(Rational::new(1, 3)),
+            PartialScore::ThreeOfAKind(Rational::new(1, 3)),
+            PartialScore::TwoPair(Rational::new(1, 3)),
+        ]
+    );
+    let result = estimate(&hand, &deck, 1, 100_000, &mut StdRng::seed_from_u64(1));
+    assert!(result.contains(22.0 / 3.0, 4.0), "{result:?}");
+}
+
+// Past x19 a royal flush would be capped.
+#[test]
+#[should_panic]
+fn test_estimate_capped() {
+    let deck = Deck::new();
+    estimate(&[], &deck, UNCAPPED + 1, 10, &mut StdRng::seed_from_u64(1));
+}
+
+#[test]
+fn test_cross_check() {
+    use crate::Rank::*;
+    use crate::Suit::*;
+
+    let full_hand = FullHand(
+        Card(Ace, Heart),
+        Card(Ace, Diamond),
+        Card(Three, Heart),
+        Card(Three, Diamond),
+        Card(Four, Diamond),
+    );
+    let deck = vec![
+        Card(Four, Heart),
+        Card(Three, Spade),
+        Card(Ace, Spade),
+        Card(King, Club),
+        Card(Seven, Heart),
+        Card(Seven, Spade),
+    ]
+    .into();
+
+    let result = cross_check(&full_hand, &deck, 1, 20_000, 42);
+    assert_eq!(result.len(), 32);
+    for comparison in &result {
+        assert!(comparison.agrees(4.0), "{:?}", comparison.keep);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::Deck;
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::MAX_MULTIPLIER;
+use crate::Score;
+use crate::all_deals;
+use crate::all_deals::CATEGORIES;
+use crate::all_deals::HOLDS;
+use crate::par::*;
+use crate::paytable::Paytable;
+use crate::tree_check;
+
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+use rational::Rational;
+
+pub const MAX: i32 = MAX_MULTIPLIER;
+
+// The multipliers at which some category's payout first reaches the cap,
+// lowest first.
+pub fn caps(paytable: &Paytable) -> Vec<i32> {
+    paytable
+        .pays
+        .iter()
+        .filter(|&&pay| pay > 0)
+        .map(|&pay| (MAX + pay - 1) / pay)
+        .filter(|&m| m <= MAX)
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+// One hold's draws, tallied by category, which fixes its expected payout
+// at every multiplier.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    pub mask: usize,
+    pub draws: i128,
+    pub counts: [i128; CATEGORIES],
+}
+
+impl Curve {
+    // Expected chips paid at a stake of `multiplier`.
+    pub fn expected(&self, paytable: &Paytable, multiplier: i32) -> Rational {
+        Rational::new(self.value(paytable, multiplier), self.draws)
+    }
+
+    fn value(&self, paytable: &Paytable, multiplier: i32) -> i128 {
+        Score::ALL
+            .iter()
+            .zip(&self.counts)
+            .map(|(&score, n)| n * paytable.payout(score, multiplier) as i128)
+            .sum()
+    }
+
+    // value = fixed + slope * multiplier, for multipliers in `region`.
+    fn line(&self, paytable: &Paytable, region: (i32, i32)) -> (i128, i128) {
+        Score::ALL
+            .iter()
+            .zip(&self.counts)
+            .fold((0, 0), |(fixed, slope), (&score, n)| {
+                if paytable.pay(score) * region.0 >= MAX {
+                    (fixed + n * MAX as i128, slope)
+                } else {
+                    (fixed, slope + n * paytable.pay(score) as i128)
+                }
+            })
+    }
+}
+
+// The 32 holds of `full_hand` drawing from `deck`, by mask.
+pub fn curves(full_hand: &FullHand, deck: &Deck) -> Vec<Curve> {
+    (0..HOLDS)
+        .map(|mask| {
+            let keep = full_hand.keep(mask);
+            let draws = (0..HAND_SIZE - keep.len())
+                .fold(1i128, |n, i| n * (deck.size() - i) as i128 / (i as i128 + 1));
+            let mut counts = [0; CATEGORIES];
+            for outcome in tree_check::check(&keep, deck) {
+                let probability = outcome.probability();
+                counts[outcome.category() as usize] =
+                    probability.numerator() * draws / probability.denominator();
+            }
+            Curve { mask, draws, counts }
+        })
+        .collect()
+}
+
+fn table_curves(table: &all_deals::Table, full_hand: &FullHand) -> Vec<Curve> {
+    table
+        .hold_counts(full_hand)
+        .iter()
+        .enumerate()
+        .map(|(mask, counts)| Curve {
+            mask,
+            draws: all_deals::draws(mask) as i128,
+            counts: counts.map(|n| n as i128),
+        })
+        .collect()
+}
+
+// A run of multipliers, inclusive, over which one hold stays best.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub from: i32,
+    pub to: i32,
+    pub best: usize,
+}
+
+// Where the best of `curves` changes, over every multiplier from 1 to the
+// cap. Ties go to the hold already best, else the lowest mask.
+//
+// Each curve is linear in the multiplier between the points where another
+// category hits the cap, so within those regions the best hold only
+// changes where its line is crossed, which is solved for directly instead
+// of trying all 9999 multipliers.
+pub fn segments(curves: &[Curve], paytable: &Paytable) -> Vec<Segment> {
+    let mut starts: Vec<i32> = [1].into_iter().chain(caps(paytable)).dedup().collect();
+    starts.push(MAX + 1);
+
+    let mut segments: Vec<Segment> = Vec::new();
+    for (&start, &end) in starts.iter().tuple_windows() {
+        let region = (start, end - 1);
+        let lines: Vec<(i128, i128)> = curves.iter().map(|curve| curve.line(paytable, region)).collect();
+        let at = |i: usize, m: i128| lines[i].0 + lines[i].1 * m;
+        // a beats b at m, cross-multiplied by their draws.
+        let beats = |a: usize, b: usize, m: i128| at(a, m) * curves[b].draws > at(b, m) * curves[a].draws;
+        let best_at = |m: i128, current: Option<usize>| {
+            (0..curves.len())
+                .reduce(|best, i| if beats(i, best, m) { i } else { best })
+                .map(|best| match current {
+                    Some(current) if !beats(best, current, m) => current,
+                    _ => best,
+                })
+                .unwrap()
+        };
+
+        let mut m = start as i128;
+        let mut best = best_at(m, segments.last().map(|segment| segments_index(curves, segment.best)));
+        loop {
+            // The first multiplier at which any line strictly passes the best:
+            // m * k > c, with k the difference in slopes.
+            let next = (0..curves.len())
+                .filter_map(|i| {
+                    let k = lines[i].1 * curves[best].draws - lines[best].1 * curves[i].draws;
+                    let c = lines[best].0 * curves[i].draws - lines[i].0 * curves[best].draws;
+                    (k > 0).then(|| c.div_euclid(k) + 1)
+                })
+                .filter(|&crossing| crossing > m && crossing <= region.1 as i128)
+                .min();
+
+            let to = next.map_or(region.1, |crossing| crossing as i32 - 1);
+            match segments.last_mut() {
+                Some(last) if last.best == curves[best].mask && last.to + 1 == m as i32 => last.to = to,
+                _ => segments.push(Segment {
+                    from: m as i32,
+                    to,
+                    best: curves[best].mask,
+                }),
+            }
+
+            let Some(crossing) = next else {
+                break;
+            };
+            m = crossing;
+            best = best_at(m, Some(best));
+        }
+    }
+    segments
+}
+
+fn segments_index(curves: &[Curve], mask: usize) -> usize {
+    curves.iter().position(|curve| curve.mask == mask).unwrap()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Thresholds {
+    pub deals: u64,
+    // Deals whose best hold is not the same at every multiplier.
+    pub changing: u64,
+    // By multiplier: deals whose best hold changes on reaching it.
+    pub at: BTreeMap<i32, u64>,
+    // A deal for each threshold, the first met.
+    pub examples: BTreeMap<i32, (FullHand, Vec<Segment>)>,
+}
+
+// `segments` for every deal in `classes`, tallied by where they change.
+pub fn thresholds(
+    table: &all_deals::Table,
+    classes: &[(FullHand, u32)],
+    paytable: &Paytable,
+) -> Thresholds {
+    let empty = || Thresholds {
+        deals: 0,
+        changing: 0,
+        at: BTreeMap::new(),
+        examples: BTreeMap::new(),
+    };
+
+    classes
+        .par_iter()
+        .fold(empty, |mut thresholds, (full_hand, weight)| {
+            let segments = segments(&table_curves(table, full_hand), paytable);
+            thresholds.deals += *weight as u64;
+            if segments.len() > 1 {
+                thresholds.changing += *weight as u64;
+            }
+            for segment in &segments[1..] {
+                *thresholds.at.entry(segment.from).or_default() += *weight as u64;
+                thresholds
+                    .examples
+                    .entry(segment.from)
+                    .or_insert_with(|| (full_hand.clone(), segments.clone()));
+            }
+            thresholds
+        })
+        .reduce(empty, |mut a, b| {
+            a.deals += b.deals;
+            a.changing += b.changing;
+            for (m, deals) in b.at {
+                *a.at.entry(m).or_default() += deals;
+            }
+            for (m, example) in b.examples {
+                a.examples.entry(m).or_insert(example);
+            }
+            a
+        })
+}
+
+#[test]
+fn test_segments() {
+    use crate::parse_input::parse;
+
+    // A made flush against four to a royal: the royal draw's 500 is
+    // capped from x20, and from x43 keeping the flush wins.
+    let full_hand = parse("10H JH QH KH 2H").unwrap();
+    let deck = (&full_hand).into();
+    let curves = curves(&full_hand, &deck);
+    let paytable = Paytable::default();
+
+    for mask in [0b01111, 0b11111, 0b00011] {
+        let outcomes = tree_check::check(&full_hand.keep(mask), &deck);
+        for m in [1, 42, 43, MAX] {
+            assert_eq!(curves[mask].expected(&paytable, m), tree_check::expected_score(&outcomes, m));
+        }
+    }
+
+    let segments = segments(&curves, &paytable);
+    assert_eq!(segments.first().unwrap().from, 1);
+    assert_eq!(segments.last().unwrap().to, MAX);
+    assert!(segments.windows(2).all(|pair| pair[0].to + 1 == pair[1].from));
+
+    // Check every boundary against brute force either side of it.
+    let best_at = |m: i32| {
+        (0..HOLDS)
+            .max_by(|&a, &b| {
+                curves[a].expected(&paytable, m).cmp(&curves[b].expected(&paytable, m)).then(b.cmp(&a))
+            })
+            .unwrap()
+    };
+    for segment in &segments {
+        for m in [segment.from, segment.to] {
+            let best = best_at(m);
+            assert_eq!(
+                curves[best].expected(&paytable, m),
+                curves[segment.best].expected(&paytable, m),
+                "x{m}"
+            );
+        }
+    }
+    let spans: Vec<_> = segments.iter().map(|s| (s.from, s.to, s.best)).collect();
+    assert_eq!(spans, [(1, 42, 0b01111), (43, MAX, 0b11111)]);
+
+    // A made straight flush is never worth breaking for the royal.
+    let full_hand = parse("9H 10H JH QH KH").unwrap();
+    let segments = self::segments(&self::curves(&full_hand, &(&full_hand).into()), &paytable);
+    let spans: Vec<_> = segments.iter().map(|s| (s.from, s.to, s.best)).collect();
+    assert_eq!(spans, [(1, MAX, 0b11111)]);
+}
+
+#[test]
+fn test_thresholds() {
+    use crate::parse_input::parse;
+
+    let table = all_deals::Table::new();
+    let classes = [
+        (parse("10H JH QH KH 2H").unwrap(), 4),
+        (parse("2C 2D 9H JS KC").unwrap(), 12),
+    ];
+    let thresholds = thresholds(&table, &classes, &Paytable::default());
+    assert_eq!(thresholds.deals, 16);
+    assert_eq!(thresholds.changing, 4);
+    assert_eq!(thresholds.at, BTreeMap::from([(43, 4)]));
+
+    let full_hand = &classes[0].0;
+    let expected = segments(&curves(full_hand, &full_hand.into()), &Paytable::default());
+    let (example, segments) = thresholds.examples.values().next().unwrap();
+    assert_eq!(example, full_hand);
+    assert_eq!(segments, &expected);
+}
+
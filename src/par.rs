@@ -0,0 +1,75 @@
+// rayon's parallel iterators where there are threads, and sequential ones
+// of the same shape where there aren't (wasm), so callers just use
+// `crate::par::*` and don't care which they get.
+
+#[cfg(feature = "parallel")]
+pub(crate) use rayon::prelude::*;
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) use sequential::*;
+
+// Only what the crate calls: into_par_iter/par_iter, then map, enumerate,
+// collect, and rayon's two-closure fold and reduce.
+#[cfg(not(feature = "parallel"))]
+mod sequential {
+    use std::iter;
+
+    pub(crate) struct Sequential<I>(I);
+
+    pub(crate) trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Sequential<Self::IntoIter> {
+            Sequential(self.into_iter())
+        }
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+
+    pub(crate) trait IntoParallelRefIterator<'a> {
+        type Iter;
+
+        fn par_iter(&'a self) -> Self::Iter;
+    }
+
+    impl<'a, T: 'a + ?Sized> IntoParallelRefIterator<'a> for T
+    where
+        &'a T: IntoIterator,
+    {
+        type Iter = Sequential<<&'a T as IntoIterator>::IntoIter>;
+
+        fn par_iter(&'a self) -> Self::Iter {
+            Sequential(self.into_iter())
+        }
+    }
+
+    impl<I: Iterator> Sequential<I> {
+        pub(crate) fn map<B, F: FnMut(I::Item) -> B>(self, f: F) -> Sequential<iter::Map<I, F>> {
+            Sequential(self.0.map(f))
+        }
+
+        pub(crate) fn enumerate(self) -> Sequential<iter::Enumerate<I>> {
+            Sequential(self.0.enumerate())
+        }
+
+        pub(crate) fn collect<C: FromIterator<I::Item>>(self) -> C {
+            self.0.collect()
+        }
+
+        // rayon folds each thread's share from a fresh identity and leaves
+        // the results to be reduced; here there's just the one share.
+        pub(crate) fn fold<T, Id, F>(self, identity: Id, f: F) -> Sequential<iter::Once<T>>
+        where
+            Id: Fn() -> T,
+            F: FnMut(T, I::Item) -> T,
+        {
+            Sequential(iter::once(self.0.fold(identity(), f)))
+        }
+
+        pub(crate) fn reduce<Id, F>(self, identity: Id, f: F) -> I::Item
+        where
+            Id: Fn() -> I::Item,
+            F: FnMut(I::Item, I::Item) -> I::Item,
+        {
+            self.0.fold(identity(), f)
+        }
+    }
+}
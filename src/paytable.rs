@@ -0,0 +1,444 @@
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::MAX_MULTIPLIER;
+use crate::Score;
+use crate::all_deals;
+use crate::all_deals::CATEGORIES;
+use crate::all_deals::HOLDS;
+use crate::par::*;
+
+use std::fmt;
+use std::str::FromStr;
+
+use rational::Rational;
+
+// What each category pays per chip wagered, by `Score as usize`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Paytable {
+    pub pays: [i32; CATEGORIES],
+}
+
+impl Paytable {
+    pub fn pay(&self, score: Score) -> i32 {
+        self.pays[score as usize]
+    }
+
+    // Chips paid at a stake of `multiplier`, capped as the game does.
+    pub fn payout(&self, score: Score, multiplier: i32) -> i32 {
+        std::cmp::min(self.pay(score) * multiplier, MAX_MULTIPLIER)
+    }
+}
+
+// The game's own paytable.
+impl Default for Paytable {
+    fn default() -> Self {
+        Paytable {
+            pays: Score::ALL.map(|score| u16::from(score) as i32),
+        }
+    }
+}
+
+// Pair first, up to royal flush: "2,3,4,8,10,15,50,100,500".
+impl fmt::Display for Paytable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pays: Vec<String> = self.pays.iter().map(i32::to_string).collect();
+        write!(f, "{}", pays.join(","))
+    }
+}
+
+impl FromStr for Paytable {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        // Pays can't be negative, or so big the chips at the top stake
+        // overflow before they're capped.
+        let pays: Vec<i32> = input
+            .split([',', ' '])
+            .filter(|pay| !pay.is_empty())
+            .map(|pay| match pay.parse::<i32>() {
+                Ok(pay) if pay >= 0 && pay.checked_mul(MAX_MULTIPLIER).is_some() => Ok(pay),
+                _ => Err(()),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Paytable {
+            pays: pays.try_into().map_err(|_| ())?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Share {
+    // `None` for hands that pay nothing.
+    pub category: Option<Score>,
+    // Chance of ending the round on it.
+    pub frequency: Rational,
+    // What it adds to the return per chip.
+    pub contribution: Rational,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rtp {
+    pub paytable: Paytable,
+    pub multiplier: i32,
+    // Expected chips back per chip wagered, under optimal play.
+    pub rtp: Rational,
+    // Best paying first, then nothing.
+    pub shares: Vec<Share>,
+}
+
+// Weighted counts of each category, then of chips paid, by the number of
+// cards swapped (which fixes the number of draws).
+type Totals = [[i128; CATEGORIES + 1]; HAND_SIZE + 1];
+
+// How many deals in a sweep's flipped counts get kept as examples.
+const EXAMPLES: usize = 5;
+
+// A deal whose best hold under one paytable does worse than the best under
+// another, by mask.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flip {
+    pub full_hand: FullHand,
+    // How many of the C(52, 5) deals this one stands for.
+    pub weight: u32,
+    pub before: usize,
+    pub after: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant {
+    pub rtp: Rtp,
+    // Deals whose best hold changes from the base paytable's.
+    pub flipped: u64,
+    pub deals: u64,
+    // The first few of them.
+    pub flips: Vec<Flip>,
+}
+
+struct Pass {
+    totals: Totals,
+    flipped: u64,
+    flips: Vec<Flip>,
+}
+
+// Exact return to player over every deal in `classes` (suit classes with
+// their weights, as `all_deals::deal_classes` gives), playing each one's
+// best hold for `paytable` at a stake of `multiplier`.
+//
+// This is `tree_check::best_score` averaged over the classes, but scored
+// from the all-deals table with integer arithmetic, so a whole paytable
+// takes seconds rather than days.
+// Where holds tie, the frequencies follow the one with the lowest mask.
+pub fn rtp(
+    table: &all_deals::Table,
+    classes: &[(FullHand, u32)],
+    paytable: &Paytable,
+    multiplier: i32,
+) -> Rtp {
+    let passes = solve(table, classes, &[*paytable], multiplier);
+    summarise(&passes[0].totals, deals(classes), paytable, multiplier)
+}
+
+// `rtp` for each of `variants`, along with which deals they play
+// differently from `base`. Ties don't count as a change: a deal flips only
+// if the hold that was best for `base` now does worse.
+pub fn sweep(
+    table: &all_deals::Table,
+    classes: &[(FullHand, u32)],
+    base: &Paytable,
+    variants: &[Paytable],
+    multiplier: i32,
+) -> Vec<Variant> {
+    let paytables: Vec<Paytable> = std::iter::once(*base).chain(variants.iter().copied()).collect();
+    let deals = deals(classes);
+
+    solve(table, classes, &paytables, multiplier)
+        .into_iter()
+        .zip(&paytables)
+        .skip(1)
+        .map(|(pass, paytable)| Variant {
+            rtp: summarise(&pass.totals, deals, paytable, multiplier),
+            flipped: pass.flipped,
+            deals: deals as u64,
+            flips: pass.flips,
+        })
+        .collect()
+}
+
+// The least `category` can pay, other pays as in `base`, for an RTP of at
+// least `target`, with what that RTP comes to. Optimal play never returns
+// less when a pay goes up, so this bisects. None if no pay up to the cap
+// gets there.
+pub fn solve_pay(
+    table: &all_deals::Table,
+    classes: &[(FullHand, u32)],
+    base: &Paytable,
+    category: Score,
+    target: Rational,
+    multiplier: i32,
+) -> Option<(i32, Rtp)> {
+    let with = |pay: i32| {
+        let mut paytable = *base;
+        paytable.pays[category as usize] = pay;
+        rtp(table, classes, &paytable, multiplier)
+    };
+
+    let (mut low, mut high) = (0, MAX_MULTIPLIER);
+    let mut best = with(high);
+    if best.rtp < target {
+        return None;
+    }
+    // Invariant: `high` gets there, anything below `low` doesn't.
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let result = with(middle);
+        if result.rtp >= target {
+            high = middle;
+            best = result;
+        } else {
+            low = middle + 1;
+        }
+    }
+    Some((high, best))
+}
+
+fn deals(classes: &[(FullHand, u32)]) -> i128 {
+    classes.iter().map(|(_, weight)| *weight as i128).sum()
+}
+
+// Chips a hold pays over all its draws, from its category counts.
+fn value(counts: &[i32; CATEGORIES], payouts: &[i64; CATEGORIES]) -> i64 {
+    counts.iter().zip(payouts).map(|(&n, pay)| n as i64 * pay).sum()
+}
+
+// The hold with the best expected payout; the lowest mask of any that tie.
+fn best_hold(counts: &[[i32; CATEGORIES]; HOLDS], payouts: &[i64; CATEGORIES]) -> usize {
+    (0..HOLDS)
+        .reduce(|best, mask| if beats(counts, payouts, mask, best) { mask } else { best })
+        .unwrap()
+}
+
+// Whether hold `a` expects strictly more than hold `b`, comparing
+// value / draws without leaving the integers.
+fn beats(counts: &[[i32; CATEGORIES]; HOLDS], payouts: &[i64; CATEGORIES], a: usize, b: usize) -> bool {
+    value(&counts[a], payouts) as i128 * all_deals::draws(b) as i128
+        > value(&counts[b], payouts) as i128 * all_deals::draws(a) as i128
+}
+
+// One pass over `classes` for every paytable at once, so each deal's hold
+// counts are only worked out once. Flips are against the first paytable.
+fn solve(
+    table: &all_deals::Table,
+    classes: &[(FullHand, u32)],
+    paytables: &[Paytable],
+    multiplier: i32,
+) -> Vec<Pass> {
+    let payouts: Vec<[i64; CATEGORIES]> = paytables
+        .iter()
+        .map(|paytable| Score::ALL.map(|score| paytable.payout(score, multiplier) as i64))
+        .collect();
+    let empty = || -> Vec<Pass> {
+        paytables
+            .iter()
+            .map(|_| Pass {
+                totals: [[0; CATEGORIES + 1]; HAND_SIZE + 1],
+                flipped: 0,
+                flips: Vec::new(),
+            })
+            .collect()
+    };
+
+    classes
+        .par_iter()
+        .fold(empty, |mut passes, (full_hand, weight)| {
+            let counts = table.hold_counts(full_hand);
+            let before = best_hold(&counts, &payouts[0]);
+
+            for (pass, payouts) in passes.iter_mut().zip(&payouts) {
+                let best = best_hold(&counts, payouts);
+                let swaps = HAND_SIZE - best.count_ones() as usize;
+                for (total, &n) in pass.totals[swaps].iter_mut().zip(&counts[best]) {
+                    *total += *weight as i128 * n as i128;
+                }
+                pass.totals[swaps][CATEGORIES] += *weight as i128 * value(&counts[best], payouts) as i128;
+
+                if beats(&counts, payouts, best, before) {
+                    pass.flipped += *weight as u64;
+                    if pass.flips.len() < EXAMPLES {
+                        pass.flips.push(Flip {
+                            full_hand: full_hand.clone(),
+                            weight: *weight,
+                            before,
+                            after: best,
+                        });
+                    }
+                }
+            }
+            passes
+        })
+        .reduce(empty, |mut a, b| {
+            for (a, b) in a.iter_mut().zip(b) {
+                for (a, b) in a.totals.iter_mut().flatten().zip(b.totals.iter().flatten()) {
+                    *a += b;
+                }
+                a.flipped += b.flipped;
+                a.flips.extend(b.flips);
+                a.flips.truncate(EXAMPLES);
+            }
+            a
+        })
+}
+
+fn summarise(totals: &Totals, deals: i128, paytable: &Paytable, multiplier: i32) -> Rtp {
+    let average = |i: usize| -> Rational {
+        (0..=HAND_SIZE).fold(Rational::zero(), |sum, swaps| {
+            let draws = all_deals::draws((1 << (HAND_SIZE - swaps)) - 1) as i128;
+            sum + Rational::new(totals[swaps][i], draws * deals)
+        })
+    };
+
+    let mut shares: Vec<Share> = Score::ALL
+        .iter()
+        .rev()
+        .map(|&score| {
+            let frequency = average(score as usize);
+            Share {
+                category: Some(score),
+                frequency,
+                contribution: frequency * paytable.payout(score, multiplier) as i128
+                    / multiplier as i128,
+            }
+        })
+        .collect();
+    let paid = shares.iter().fold(Rational::zero(), |sum, share| sum + share.frequency);
+    shares.push(Share {
+        category: None,
+        frequency: Rational::one() - paid,
+        contribution: Rational::zero(),
+    });
+
+    Rtp {
+        paytable: *paytable,
+        multiplier,
+        rtp: average(CATEGORIES) / multiplier as i128,
+        shares,
+    }
+}
+
+#[test]
+fn test_paytable() {
+    let paytable = Paytable::default();
+    assert_eq!(paytable.to_string(), "2,3,4,8,10,15,50,100,500");
+    assert_eq!("2, 3, 4, 8, 10, 15, 50, 100, 500".parse(), Ok(paytable));
+    assert_eq!("2,3".parse::<Paytable>(), Err(()));
+    assert_eq!("-2,3,4,8,10,15,50,100,500".parse::<Paytable>(), Err(()));
+    assert_eq!("2,3,4,8,10,15,50,100,500000".parse::<Paytable>(), Err(()));
+    assert_eq!(paytable.pay(Score::FullHouse), 15);
+    assert_eq!(paytable.payout(Score::RoyalFlush, 30), MAX_MULTIPLIER);
+}
+
+#[test]
+fn test_rtp() {
+    use crate::parse_input::parse;
+    use crate::tree_check;
+
+    let table = all_deals::Table::new();
+    let full_hand = parse("KH 8D 3D 4D 6D").unwrap();
+    let deck = (&full_hand).into();
+    let classes = [(full_hand.clone(), 4)];
+
+    let result = rtp(&table, &classes, &Paytable::default(), 3);
+    assert_eq!(result.rtp, tree_check::best_score(full_hand.clone(), &deck, Some(3)) / 3);
+    let sum = |f: fn(&Share) -> Rational| {
+        result.shares.iter().fold(Rational::zero(), |sum, share| sum + f(share))
+    };
+    assert_eq!(sum(|share| share.frequency), Rational::one());
+    assert_eq!(sum(|share| share.contribution), result.rtp);
+    // Holding the four to a flush.
+    assert_eq!(result.shares[4].category, Some(Score::Flush));
+    assert_eq!(result.shares[4].frequency, Rational::new(9, 47));
+
+    // With flushes paying nothing it's no longer worth chasing one.
+    let mut no_flush = Paytable::default();
+    no_flush.pays[Score::Flush as usize] = 0;
+    let result = rtp(&table, &classes, &no_flush, 1);
+    assert_eq!(result.shares[4].contribution, Rational::zero());
+    assert!(result.shares[4].frequency < Rational::new(9, 47));
+}
+
+#[test]
+fn test_sweep() {
+    use crate::parse_input::parse;
+
+    let table = all_deals::Table::new();
+    let full_hand = parse("KH 8D 3D 4D 6D").unwrap();
+    let classes = [(full_hand, 4), (parse("2C 2D 9H JS KC").unwrap(), 12)];
+    let base = Paytable::default();
+
+    let mut flush = base;
+    flush.pays[Score::Flush as usize] = 1;
+    let variants = sweep(&table, &classes, &base, &[base, flush], 1);
+    assert_eq!(variants[0].rtp, rtp(&table, &classes, &base, 1));
+    assert_eq!(variants[0].flipped, 0);
+    assert_eq!(variants[1].rtp, rtp(&table, &classes, &flush, 1));
+    assert_eq!(variants[1].deals, 16);
+    assert_eq!(variants[1].flipped, 4);
+    assert_eq!(variants[1].flips[0].before, 0b11110);
+
+    // Flushes have to pay the full 10 to get back to where they were.
+    let target = variants[0].rtp.rtp;
+    let (pay, result) = solve_pay(&table, &classes, &flush, Score::Flush, target, 1).unwrap();
+    assert_eq!(pay, 10);
+    assert_eq!(result.rtp, target);
+    assert_eq!(solve_pay(&table, &classes, &base, Score::Flush, Rational::integer(1000), 1), None);
+}
+
+#[cfg(feature = "big-tests")]
+#[test]
+fn test_rtp_all_deals() {
+    let table = all_deals::Table::new();
+    let classes = all_deals::deal_classes();
+    let deals = crate::chart::solve(&table, 1);
+
+    let result = rtp(&table, &classes, &Paytable::default(), 1);
+    let best: f64 =
+        deals.iter().map(|deal| deal.weight as f64 * deal.best).sum::<f64>() / 2_598_960.0;
+    assert!((result.rtp.decimal_value() - best).abs() < 1e-9);
+}
+
+// Playing the holds `rtp` picks in `simulate` returns what `rtp` says, even
+// at x200, where straight flushes, royals and quads all hit the cap.
+#[test]
+fn test_rtp_simulated() {
+    use crate::Card;
+    use crate::simulate::simulate;
+    use crate::strategy::Context;
+    use crate::strategy::Strategy;
+
+    struct Best<'a> {
+        table: &'a all_deals::Table,
+        payouts: [i64; CATEGORIES],
+    }
+
+    impl Strategy for Best<'_> {
+        fn name(&self) -> &str {
+            "rtp's best hold"
+        }
+
+        fn hold<'a>(&mut self, full_hand: &'a FullHand, _context: &Context) -> Vec<&'a Card> {
+            full_hand.keep(best_hold(&self.table.hold_counts(full_hand), &self.payouts))
+        }
+    }
+
+    let multiplier = 200;
+    let table = all_deals::Table::new();
+    let paytable = Paytable::default();
+    let expected = rtp(&table, &all_deals::deal_classes(), &paytable, multiplier).rtp.decimal_value();
+
+    let mut best = Best {
+        table: &table,
+        payouts: Score::ALL.map(|score| paytable.payout(score, multiplier) as i64),
+    };
+    let report = simulate(&mut best, 200_000, multiplier, 7);
+    // About four standard errors.
+    assert!((report.return_to_player() - expected).abs() < 0.03, "{} against {expected}", report.return_to_player());
+}
@@ -0,0 +1,188 @@
+use crate::Card;
+use crate::Deck;
+use crate::FullHand;
+use crate::MAX_MULTIPLIER;
+use crate::Suit;
+use crate::parse_input;
+use crate::tree_check;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rational::Rational;
+
+// A Python extension module, `check`, for notebooks. Build with
+// `cargo build --release --features python` and import the library as
+// check.so (or use maturin). Values come back as fractions.Fraction, so
+// they stay exact.
+//
+//   >>> import check
+//   >>> hand = check.parse("KH 8D 3D 4D 6D")
+//   >>> check.ranked_swap_values(hand)[0]
+//   ([Card('8D'), Card('3D'), Card('4D'), Card('6D')], Fraction(114, 47))
+
+#[pyclass(name = "Card", module = "check", frozen, eq, skip_from_py_object)]
+#[derive(Clone, PartialEq)]
+pub struct PyCard(Card);
+
+#[pymethods]
+impl PyCard {
+    #[new]
+    fn new(text: &str) -> PyResult<Self> {
+        match parse_input::parse_cards(text).as_deref() {
+            Ok([card]) => Ok(PyCard(card.clone())),
+            _ => Err(PyValueError::new_err(format!("Not a card: {text}"))),
+        }
+    }
+
+    // 1 (ace) to 13 (king).
+    #[getter]
+    fn rank(&self) -> usize {
+        self.0.rank()
+    }
+
+    // "C", "D", "H" or "S".
+    #[getter]
+    fn suit(&self) -> &'static str {
+        match self.0.suit() {
+            Suit::Club => "C",
+            Suit::Diamond => "D",
+            Suit::Heart => "H",
+            Suit::Spade => "S",
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Card('{}')", self.0)
+    }
+}
+
+#[pyclass(name = "FullHand", module = "check", frozen)]
+pub struct PyFullHand(FullHand);
+
+#[pymethods]
+impl PyFullHand {
+    #[new]
+    fn new(text: &str) -> PyResult<Self> {
+        parse(text)
+    }
+
+    #[getter]
+    fn cards(&self) -> Vec<PyCard> {
+        self.0.cards().map(|card| PyCard(card.clone())).to_vec()
+    }
+
+    // The cards a hold keeps: bit i of `mask` is card i.
+    fn keep(&self, mask: usize) -> PyResult<Vec<PyCard>> {
+        if mask >= 1 << 5 {
+            return Err(PyValueError::new_err("A hold's mask is 0-31"));
+        }
+        Ok(self.0.keep(mask).into_iter().map(|card| PyCard(card.clone())).collect())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.cards().map(|card| card.to_string()).join(" ")
+    }
+
+    fn __repr__(&self) -> String {
+        format!("FullHand('{}')", self.__str__())
+    }
+}
+
+// "KH 8D 3D 4D 6D", or anything else the CLI accepts.
+#[pyfunction]
+fn parse(text: &str) -> PyResult<PyFullHand> {
+    parse_input::parse(text)
+        .map(PyFullHand)
+        .map_err(|()| PyValueError::new_err(format!("Not a hand of five different cards: {text}")))
+}
+
+fn fraction(py: Python<'_>, value: Rational) -> PyResult<Py<PyAny>> {
+    let fraction = py.import("fractions")?.getattr("Fraction")?;
+    Ok(fraction.call1((value.numerator(), value.denominator()))?.unbind())
+}
+
+fn deck(hand: &FullHand, dead: Option<Vec<PyRef<'_, PyCard>>>) -> Deck {
+    dead.into_iter()
+        .flatten()
+        .fold(hand.into(), |deck: Deck, card| deck.take_card(&card.0))
+}
+
+fn check_multiplier(multiplier: i32) -> PyResult<()> {
+    if (1..=MAX_MULTIPLIER).contains(&multiplier) {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err("The multiplier is 1-9999"))
+    }
+}
+
+// Every hold, best first, as (cards kept, expected chips). `dead` are
+// cards known to be out of the deck besides the hand.
+#[pyfunction]
+#[pyo3(signature = (hand, multiplier = 1, dead = None))]
+fn ranked_swap_values(
+    py: Python<'_>,
+    hand: &PyFullHand,
+    multiplier: i32,
+    dead: Option<Vec<PyRef<'_, PyCard>>>,
+) -> PyResult<Vec<(Vec<PyCard>, Py<PyAny>)>> {
+    check_multiplier(multiplier)?;
+    let deck = deck(&hand.0, dead);
+    // The enumeration is all Rust and runs across threads; other Python
+    // threads can carry on meanwhile.
+    let ranked: Vec<(Vec<Card>, Rational)> = py.detach(|| {
+        tree_check::ranked_swap_values(&hand.0, &deck, multiplier)
+            .into_iter()
+            .map(|(keep, value)| (keep.into_iter().cloned().collect(), value))
+            .collect()
+    });
+    ranked
+        .into_iter()
+        .map(|(keep, value)| Ok((keep.into_iter().map(PyCard).collect(), fraction(py, value)?)))
+        .collect()
+}
+
+// Chance of each category after holding `mask` (bit i keeps card i), by
+// name, with "nothing" for the rest.
+#[pyfunction]
+#[pyo3(signature = (hand, mask, dead = None))]
+fn odds<'py>(
+    py: Python<'py>,
+    hand: &PyFullHand,
+    mask: usize,
+    dead: Option<Vec<PyRef<'py, PyCard>>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    if mask >= 1 << 5 {
+        return Err(PyValueError::new_err("A hold's mask is 0-31"));
+    }
+    let deck = deck(&hand.0, dead);
+    let outcomes: Vec<(String, Rational)> = py.detach(|| {
+        tree_check::check(&hand.0.keep(mask), &deck)
+            .iter()
+            .map(|outcome| (outcome.category().to_string(), outcome.probability()))
+            .collect()
+    });
+
+    let odds = PyDict::new(py);
+    let mut nothing = Rational::one();
+    for (category, probability) in outcomes {
+        nothing -= probability;
+        odds.set_item(category, fraction(py, probability)?)?;
+    }
+    odds.set_item("nothing", fraction(py, nothing)?)?;
+    Ok(odds)
+}
+
+#[pymodule]
+fn check(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyCard>()?;
+    module.add_class::<PyFullHand>()?;
+    module.add_function(wrap_pyfunction!(parse, module)?)?;
+    module.add_function(wrap_pyfunction!(ranked_swap_values, module)?)?;
+    module.add_function(wrap_pyfunction!(odds, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,345 @@
+use crate::Deck;
+use crate::FullHand;
+use crate::hold;
+use crate::luck;
+use crate::parse_input;
+use crate::server;
+use crate::server::Hand;
+use crate::tree_check;
+
+use std::collections::HashMap;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+
+use rational::Rational;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+
+// Rankings kept between calls; past this many the cache starts over.
+const CACHE_SIZE: usize = 4096;
+
+// JSON-RPC 2.0 error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// Line-delimited JSON-RPC 2.0, one request (or batch) per line, for hosts
+// that keep one warm process rather than scraping the CLI's text. Methods:
+//
+//   parse           {"hand"}
+//   rank            {"hand", "multiplier"?, "dead"?, "top"?}
+//   analyze         {"hand", "multiplier"?, "dead"?}
+//   odds            {"hand", "hold", "multiplier"?, "dead"?}
+//   session.record  {"dealt", "drawn", "multiplier"?}
+//   session.stats
+//   session.reset
+//
+// Params and results are shaped as in the HTTP API.
+pub struct Rpc {
+    session: luck::Session,
+    // By hand text, multiplier and dead cards.
+    cache: HashMap<(String, i32, Vec<String>), Ranking>,
+}
+
+// Each hold's mask and value, best first.
+type Ranking = Vec<(usize, Rational)>;
+
+#[derive(Deserialize)]
+struct Played {
+    dealt: String,
+    drawn: String,
+    #[serde(default = "one")]
+    multiplier: i32,
+}
+
+fn one() -> i32 {
+    1
+}
+
+type Error = (i64, String);
+
+fn invalid_params(message: String) -> Error {
+    (INVALID_PARAMS, message)
+}
+
+impl Rpc {
+    pub fn new() -> Self {
+        Rpc {
+            session: luck::Session::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    // The response line for one line of input, if any: notifications and
+    // all-notification batches get none.
+    pub fn handle_line(&mut self, line: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(line) {
+            Err(error) => Some(failure(Value::Null, (PARSE_ERROR, error.to_string()))),
+            Ok(Value::Array(batch)) if batch.is_empty() => {
+                Some(failure(Value::Null, (INVALID_REQUEST, "Empty batch".to_string())))
+            }
+            Ok(Value::Array(batch)) => {
+                let responses: Vec<Value> = batch.into_iter().filter_map(|call| self.call(call)).collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(call) => self.call(call),
+        };
+        response.map(|response| response.to_string())
+    }
+
+    fn call(&mut self, call: Value) -> Option<Value> {
+        let id = call.get("id").cloned();
+        let method = call.get("method").and_then(Value::as_str);
+        let (Some("2.0"), Some(method)) = (call.get("jsonrpc").and_then(Value::as_str), method) else {
+            return Some(failure(
+                id.unwrap_or(Value::Null),
+                (INVALID_REQUEST, "Not a JSON-RPC 2.0 request".to_string()),
+            ));
+        };
+        let params = call.get("params").cloned().unwrap_or(json!({}));
+
+        let result = match method {
+            "parse" => params_as(params).and_then(|hand| parse(&hand)),
+            "rank" => params_as(params).and_then(|hand| self.rank(&hand)),
+            "analyze" => params_as(params).and_then(|hand| self.analyze(&hand)),
+            "odds" => params_as(params).and_then(|hand| odds(&hand)),
+            "session.record" => params_as(params).and_then(|played| self.record(&played)),
+            "session.stats" => Ok(self.stats()),
+            "session.reset" => {
+                self.session = luck::Session::new();
+                Ok(self.stats())
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("No method {method}"))),
+        };
+
+        // Requests without an id are notifications, answered with nothing.
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => failure(id, error),
+        })
+    }
+
+    fn ranked(&mut self, request: &Hand) -> Result<(FullHand, Deck, &Ranking), Error> {
+        let (full_hand, deck) = server::deal(request).map_err(|(_, error)| invalid_params(error))?;
+        let key = (request.hand.clone(), request.multiplier, request.dead.clone());
+        if self.cache.len() >= CACHE_SIZE && !self.cache.contains_key(&key) {
+            self.cache.clear();
+        }
+        let ranked = self.cache.entry(key).or_insert_with(|| {
+            tree_check::ranked_swap_values(&full_hand, &deck, request.multiplier)
+                .into_iter()
+                .map(|(keep, value)| (full_hand.mask(&keep), value))
+                .collect()
+        });
+        Ok((full_hand, deck, ranked))
+    }
+
+    fn rank(&mut self, request: &Hand) -> Result<Value, Error> {
+        let multiplier = request.multiplier;
+        let top = request.top;
+        let (full_hand, _, ranked) = self.ranked(request)?;
+        let holds: Vec<Value> = ranked
+            .iter()
+            .take(top.unwrap_or(ranked.len()))
+            .map(|(mask, value)| server::hold_json(&full_hand, *mask, value, multiplier))
+            .collect();
+        Ok(json!({
+            "hand": server::text(&full_hand.cards()),
+            "multiplier": multiplier,
+            "holds": holds,
+        }))
+    }
+
+    // The best hold, its odds, and how far ahead of the next best it is.
+    fn analyze(&mut self, request: &Hand) -> Result<Value, Error> {
+        let multiplier = request.multiplier;
+        let (full_hand, deck, ranked) = self.ranked(request)?;
+        let (mask, value) = ranked[0];
+        let runner_up = ranked.iter().find(|(_, other)| *other < value).map_or(value, |(_, other)| *other);
+        let outcomes = tree_check::check(&full_hand.keep(mask), &deck);
+
+        let mut best = server::hold_json(&full_hand, mask, &value, multiplier);
+        best["odds"] = server::odds(&outcomes);
+        Ok(json!({
+            "hand": server::text(&full_hand.cards()),
+            "multiplier": multiplier,
+            "best": best,
+            "margin": (value - runner_up).decimal_value() / multiplier as f64,
+        }))
+    }
+
+    fn record(&mut self, played: &Played) -> Result<Value, Error> {
+        let parse = |hand: &str| {
+            parse_input::parse(hand).map_err(|()| invalid_params(format!("Not a hand: {hand}")))
+        };
+        let dealt = parse(&played.dealt)?;
+        let draw = self
+            .session
+            .record(&dealt, &(&dealt).into(), &parse(&played.drawn)?, played.multiplier)
+            .map_err(|error| invalid_params(error.to_string()))?;
+        Ok(json!({
+            "hold": hold::positions(draw.keep),
+            "category": draw.score.map_or("nothing".to_string(), |score| score.to_string()),
+            "payout": draw.payout,
+            "expected": draw.expected.decimal_value(),
+            "session": self.stats(),
+        }))
+    }
+
+    fn stats(&self) -> Value {
+        json!({
+            "hands": self.session.hands,
+            "actual": self.session.actual,
+            "expected": self.session.expected.decimal_value(),
+            "luck": self.session.luck(),
+            "percentile": self.session.percentile(),
+        })
+    }
+}
+
+impl Default for Rpc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn params_as<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, Error> {
+    serde_json::from_value(params).map_err(|error| invalid_params(error.to_string()))
+}
+
+fn failure(id: Value, (code, message): Error) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn parse(request: &Hand) -> Result<Value, Error> {
+    let cards = parse_input::parse_cards(&request.hand)
+        .map_err(|()| invalid_params(format!("Not cards: {}", request.hand)))?;
+    Ok(json!({
+        "cards": cards.iter().map(|card| card.to_string()).collect::<Vec<_>>(),
+        "complete": parse_input::parse(&request.hand).is_ok(),
+    }))
+}
+
+fn odds(request: &Hand) -> Result<Value, Error> {
+    let (full_hand, deck) = server::deal(request).map_err(|(_, error)| invalid_params(error))?;
+    let held = request.hold.as_ref().ok_or_else(|| invalid_params("No hold given".to_string()))?;
+    let mask = hold::mask(held).map_err(|()| invalid_params("Hold positions are 1-5".to_string()))?;
+    let outcomes = tree_check::check(&full_hand.keep(mask), &deck);
+    let value = tree_check::expected_score(&outcomes, request.multiplier);
+    let mut result = server::hold_json(&full_hand, mask, &value, request.multiplier);
+    result["odds"] = server::odds(&outcomes);
+    Ok(result)
+}
+
+// Answers each line of `input` on `output` until it closes.
+pub fn run(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut rpc = Rpc::new();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = rpc.handle_line(&line) {
+            writeln!(output, "{response}")?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_rpc() {
+    let mut rpc = Rpc::new();
+    let mut call = |line: &str| -> Value { serde_json::from_str(&rpc.handle_line(line).unwrap()).unwrap() };
+
+    let parsed = call(r#"{"jsonrpc": "2.0", "id": 1, "method": "parse", "params": {"hand": "kh 8d"}}"#);
+    assert_eq!(parsed["id"], 1);
+    assert_eq!(parsed["result"]["cards"], json!(["KH", "8D"]));
+    assert_eq!(parsed["result"]["complete"], false);
+
+    let ranked = call(
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "rank",
+            "params": {"hand": "KH 8D 3D 4D 6D", "top": 2}}"#,
+    );
+    assert_eq!(ranked["result"]["holds"].as_array().unwrap().len(), 2);
+    assert_eq!(ranked["result"]["holds"][0]["hold"], json!([2, 3, 4, 5]));
+
+    let analysis = call(r#"{"jsonrpc": "2.0", "id": "a", "method": "analyze", "params": {"hand": "KH 8D 3D 4D 6D"}}"#);
+    assert_eq!(analysis["id"], "a");
+    assert_eq!(analysis["result"]["best"], {
+        let mut best = ranked["result"]["holds"][0].clone();
+        best["odds"] = analysis["result"]["best"]["odds"].clone();
+        best
+    });
+    assert!(analysis["result"]["margin"].as_f64().unwrap() > 0.0);
+
+    let odds = call(
+        r#"{"jsonrpc": "2.0", "id": 3, "method": "odds",
+            "params": {"hand": "KH 8D 3D 4D 6D", "hold": [2, 3, 4, 5]}}"#,
+    );
+    assert_eq!(odds["result"]["expected"], ranked["result"]["holds"][0]["expected"]);
+
+    let recorded = call(
+        r#"{"jsonrpc": "2.0", "id": 4, "method": "session.record",
+            "params": {"dealt": "KH 8D 3D 4D 6D", "drawn": "JD 8D 3D 4D 6D"}}"#,
+    );
+    assert_eq!(recorded["result"]["category"], "flush");
+    assert_eq!(recorded["result"]["session"]["hands"], 1);
+    let zero = call(
+        r#"{"jsonrpc": "2.0", "id": 4, "method": "session.record",
+            "params": {"dealt": "KH 8D 3D 4D 6D", "drawn": "JD 8D 3D 4D 6D", "multiplier": 0}}"#,
+    );
+    assert_eq!(zero["error"]["code"], INVALID_PARAMS);
+    let huge = call(
+        r#"{"jsonrpc": "2.0", "id": 4, "method": "session.record",
+            "params": {"dealt": "KH 8D 3D 4D 6D", "drawn": "JD 8D 3D 4D 6D", "multiplier": 2000000000}}"#,
+    );
+    assert_eq!(huge["error"]["code"], INVALID_PARAMS);
+    assert_eq!(huge["error"]["message"], "Multiplier out of range");
+    let repeated = call(
+        r#"{"jsonrpc": "2.0", "id": 4, "method": "session.record",
+            "params": {"dealt": "KH 8D 3D 4D 6D", "drawn": "8D 8D 3D 4D 6D"}}"#,
+    );
+    assert_eq!(repeated["error"]["message"], "Not a hand: 8D 8D 3D 4D 6D");
+
+    let missing = call(r#"{"jsonrpc": "2.0", "id": 5, "method": "deal"}"#);
+    assert_eq!(missing["error"]["code"], METHOD_NOT_FOUND);
+    let invalid = call(r#"{"jsonrpc": "2.0", "id": 6, "method": "rank", "params": {"hand": "KH"}}"#);
+    assert_eq!(invalid["error"]["code"], INVALID_PARAMS);
+    assert_eq!(call("{")["error"]["code"], PARSE_ERROR);
+    assert_eq!(call(r#"{"id": 7, "method": "parse"}"#)["error"]["code"], INVALID_REQUEST);
+
+    let batch = call(
+        r#"[{"jsonrpc": "2.0", "id": 8, "method": "session.stats"},
+            {"jsonrpc": "2.0", "method": "session.reset"}]"#,
+    );
+    assert_eq!(batch.as_array().unwrap().len(), 1);
+    assert_eq!(batch[0]["result"]["hands"], 1);
+    assert_eq!(rpc.handle_line(r#"{"jsonrpc": "2.0", "method": "session.reset"}"#), None);
+}
+
+#[test]
+fn test_run() {
+    let input = concat!(
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "session.stats"}"#,
+        "\n\n",
+        r#"{"jsonrpc": "2.0", "method": "session.reset"}"#,
+        "\n",
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "parse", "params": {"hand": "AS"}}"#,
+        "\n",
+    );
+    let mut output = Vec::new();
+    run(input.as_bytes(), &mut output).unwrap();
+    let lines: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["id"], 1);
+    assert_eq!(lines[1]["result"]["cards"], json!(["AS"]));
+}
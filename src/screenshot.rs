@@ -0,0 +1,356 @@
+use crate::Card;
+use crate::FullHand;
+use crate::Rank;
+use crate::Suit;
+
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use image::DynamicImage;
+use image::GrayImage;
+use image::Luma;
+use image::Rgb;
+use image::RgbImage;
+use image::imageops;
+use image::imageops::FilterType;
+
+// Reads the dealt hand off a PNG or JPEG screenshot of the poker screen.
+// Cards are the big white blobs on the table; the index in each one's
+// top-left corner is its rank above its suit, and both are matched against
+// the reference crops in src/screenshot/ by normalised cross-correlation,
+// after scaling to the same size, so the capture's resolution doesn't
+// matter.
+//
+// Experimental. The crops and the corpus in tests/screenshots/ both come
+// from `cargo run --example screenshots --features screenshot`, drawn
+// rather than captured, so `test_corpus` only shows the two agree; nothing
+// yet shows it reads the game's own screen. Recut the crops and add to the
+// corpus from real captures before relying on it.
+
+const RANK_SIZE: (u32, u32) = (24, 32);
+const SUIT_SIZE: (u32, u32) = (24, 24);
+
+const RANKS: [(Rank, &[u8]); 13] = [
+    (Rank::Ace, include_bytes!("screenshot/ranks/A.png")),
+    (Rank::Two, include_bytes!("screenshot/ranks/2.png")),
+    (Rank::Three, include_bytes!("screenshot/ranks/3.png")),
+    (Rank::Four, include_bytes!("screenshot/ranks/4.png")),
+    (Rank::Five, include_bytes!("screenshot/ranks/5.png")),
+    (Rank::Six, include_bytes!("screenshot/ranks/6.png")),
+    (Rank::Seven, include_bytes!("screenshot/ranks/7.png")),
+    (Rank::Eight, include_bytes!("screenshot/ranks/8.png")),
+    (Rank::Nine, include_bytes!("screenshot/ranks/9.png")),
+    (Rank::Ten, include_bytes!("screenshot/ranks/10.png")),
+    (Rank::Jack, include_bytes!("screenshot/ranks/J.png")),
+    (Rank::Queen, include_bytes!("screenshot/ranks/Q.png")),
+    (Rank::King, include_bytes!("screenshot/ranks/K.png")),
+];
+
+const SUITS: [(Suit, &[u8]); 4] = [
+    (Suit::Club, include_bytes!("screenshot/suits/C.png")),
+    (Suit::Diamond, include_bytes!("screenshot/suits/D.png")),
+    (Suit::Heart, include_bytes!("screenshot/suits/H.png")),
+    (Suit::Spade, include_bytes!("screenshot/suits/S.png")),
+];
+
+// How dark (255 - the darkest channel) a pixel must be to count as print,
+// so red pips count as well as black ones.
+const INK: u8 = 100;
+
+pub struct Recognised {
+    pub full_hand: FullHand,
+    // How well each card, in hand order, matched its crops: 1 is perfect,
+    // and anything much under 0.5 deserves a second look.
+    pub confidence: [f32; 5],
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Image(image::ImageError),
+    // There weren't five cards; how many there were.
+    Cards(usize),
+    // Nothing legible in this card's corner (0-based, left to right).
+    Unreadable(usize),
+    // Two cards read the same.
+    Duplicate(Card),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Error::Image(error) => write!(f, "{error}"),
+            Error::Cards(found) => write!(f, "Found {found} cards, not 5"),
+            Error::Unreadable(i) => write!(f, "Couldn't read card {}", i + 1),
+            Error::Duplicate(card) => write!(f, "Read {card} twice"),
+        }
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(error: image::ImageError) -> Self {
+        Error::Image(error)
+    }
+}
+
+pub fn open(path: &Path) -> Result<Recognised, Error> {
+    recognise(&image::open(path)?)
+}
+
+pub fn recognise(image: &DynamicImage) -> Result<Recognised, Error> {
+    let image = image.to_rgb8();
+    let regions = cards(&image);
+    if regions.len() != 5 {
+        return Err(Error::Cards(regions.len()));
+    }
+
+    let mut read: Vec<(Card, f32)> = Vec::new();
+    for (i, region) in regions.iter().enumerate() {
+        let (card, confidence) = read_card(&image, region).ok_or(Error::Unreadable(i))?;
+        if read.iter().any(|(other, _)| *other == card) {
+            return Err(Error::Duplicate(card));
+        }
+        read.push((card, confidence));
+    }
+
+    let confidence = std::array::from_fn(|i| read[i].1);
+    let mut cards = read.into_iter().map(|(card, _)| card);
+    let mut next = || cards.next().unwrap();
+    Ok(Recognised {
+        full_hand: FullHand(next(), next(), next(), next(), next()),
+        confidence,
+    })
+}
+
+#[derive(Debug)]
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+// Card stock: bright and grey, unlike the felt or the coloured UI.
+fn is_card(&Rgb([r, g, b]): &Rgb<u8>) -> bool {
+    let (low, high) = (r.min(g).min(b), r.max(g).max(b));
+    low > 190 && high - low < 40
+}
+
+// The five biggest card-shaped blobs of card stock, left to right.
+fn cards(image: &RgbImage) -> Vec<Region> {
+    let (width, height) = image.dimensions();
+    let mut seen = vec![false; (width * height) as usize];
+    let mut stack = Vec::new();
+    let mut found = Vec::new();
+
+    for start in 0..seen.len() {
+        let (x, y) = (start as u32 % width, start as u32 / width);
+        if seen[start] || !is_card(image.get_pixel(x, y)) {
+            continue;
+        }
+        seen[start] = true;
+        stack.push((x, y));
+        let mut area = 0u64;
+        let (mut left, mut right, mut top, mut bottom) = (x, x, y, y);
+        while let Some((x, y)) = stack.pop() {
+            area += 1;
+            (left, right, top, bottom) = (left.min(x), right.max(x), top.min(y), bottom.max(y));
+            let neighbours = [
+                x.checked_sub(1).map(|x| (x, y)),
+                Some((x + 1, y)),
+                y.checked_sub(1).map(|y| (x, y)),
+                Some((x, y + 1)),
+            ];
+            for (x, y) in neighbours.into_iter().flatten().filter(|&(x, y)| x < width && y < height) {
+                let i = (y * width + x) as usize;
+                if !seen[i] && is_card(image.get_pixel(x, y)) {
+                    seen[i] = true;
+                    stack.push((x, y));
+                }
+            }
+        }
+
+        let region = Region {
+            x: left,
+            y: top,
+            width: right - left + 1,
+            height: bottom - top + 1,
+        };
+        let aspect = region.height as f32 / region.width as f32;
+        let filled = area as f32 / (region.width * region.height) as f32;
+        if (1.2..=1.8).contains(&aspect) && filled > 0.6 && region.height > height / 20 {
+            found.push((area, region));
+        }
+    }
+
+    found.sort_by_key(|(area, _)| std::cmp::Reverse(*area));
+    let mut found: Vec<Region> = found.into_iter().take(5).map(|(_, region)| region).collect();
+    found.sort_by_key(|region| region.x);
+    found
+}
+
+fn ink(image: &RgbImage) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let Rgb([r, g, b]) = *image.get_pixel(x, y);
+        Luma([255 - r.min(g).min(b)])
+    })
+}
+
+// Runs of consecutive `true`s at least `min` long.
+fn runs(marks: &[bool], min: usize) -> Vec<Range<u32>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, &mark) in marks.iter().chain([&false]).enumerate() {
+        match (mark, start) {
+            (true, None) => start = Some(i),
+            (false, Some(from)) => {
+                if i - from >= min {
+                    runs.push(from as u32..i as u32);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+// The tight box around the print in `rows` of `ink`.
+fn glyph(ink: &GrayImage, rows: &Range<u32>) -> Option<Region> {
+    let columns: Vec<bool> = (0..ink.width())
+        .map(|x| rows.clone().any(|y| ink.get_pixel(x, y)[0] > INK))
+        .collect();
+    let left = columns.iter().position(|&mark| mark)? as u32;
+    let right = columns.iter().rposition(|&mark| mark)? as u32;
+    Some(Region {
+        x: left,
+        y: rows.start,
+        width: right - left + 1,
+        height: rows.end - rows.start,
+    })
+}
+
+// Zero mean and unit length, so a dot product is the correlation.
+fn normalise(mut values: Vec<f32>) -> Vec<f32> {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    values.iter_mut().for_each(|value| *value -= mean);
+    let length = values.iter().map(|value| value * value).sum::<f32>().sqrt().max(f32::EPSILON);
+    values.iter_mut().for_each(|value| *value /= length);
+    values
+}
+
+fn features(ink: &GrayImage, (width, height): (u32, u32)) -> Vec<f32> {
+    let scaled = imageops::resize(ink, width, height, FilterType::Triangle);
+    normalise(scaled.pixels().map(|&Luma([value])| value as f32).collect())
+}
+
+struct Templates {
+    ranks: Vec<(Rank, Vec<f32>)>,
+    suits: Vec<(Suit, Vec<f32>)>,
+}
+
+fn templates() -> &'static Templates {
+    static TEMPLATES: OnceLock<Templates> = OnceLock::new();
+    // The crops are stored as they look: dark print on white.
+    let load = |png: &[u8], size| {
+        let crop = image::load_from_memory(png).expect("Bad reference crop").to_rgb8();
+        features(&ink(&crop), size)
+    };
+    TEMPLATES.get_or_init(|| Templates {
+        ranks: RANKS.iter().map(|&(rank, png)| (rank, load(png, RANK_SIZE))).collect(),
+        suits: SUITS.iter().map(|&(suit, png)| (suit, load(png, SUIT_SIZE))).collect(),
+    })
+}
+
+fn best<T: Copy>(templates: &[(T, Vec<f32>)], features: &[f32]) -> (T, f32) {
+    templates
+        .iter()
+        .map(|(name, template)| (*name, template.iter().zip(features).map(|(a, b)| a * b).sum::<f32>()))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap()
+}
+
+fn read_card(image: &RgbImage, card: &Region) -> Option<(Card, f32)> {
+    // The index sits in the top-left corner, clear of the edge and the pip.
+    let corner = imageops::crop_imm(
+        image,
+        card.x + card.width * 3 / 100,
+        card.y + card.height * 2 / 100,
+        card.width * 30 / 100,
+        card.height * 40 / 100,
+    )
+    .to_image();
+    let ink = ink(&corner);
+
+    let rows: Vec<bool> = (0..ink.height())
+        .map(|y| (0..ink.width()).any(|x| ink.get_pixel(x, y)[0] > INK))
+        .collect();
+    let runs = runs(&rows, ink.height() as usize / 20);
+    let [rank, suit, ..] = runs.as_slice() else {
+        return None;
+    };
+    let (rank, suit) = (glyph(&ink, rank)?, glyph(&ink, suit)?);
+    let crop = |region: &Region| imageops::crop_imm(&ink, region.x, region.y, region.width, region.height).to_image();
+
+    // Colour settles red against black; the shape does the rest.
+    let red = (suit.x..suit.x + suit.width)
+        .flat_map(|x| (suit.y..suit.y + suit.height).map(move |y| (x, y)))
+        .filter(|&(x, y)| ink.get_pixel(x, y)[0] > INK)
+        .map(|(x, y)| {
+            let Rgb([r, g, b]) = *corner.get_pixel(x, y);
+            r as i32 - g.max(b) as i32 > 60
+        })
+        .fold((0, 0), |(red, all), is_red| (red + is_red as u32, all + 1));
+    let red = red.0 * 2 > red.1;
+    let suits: Vec<(Suit, Vec<f32>)> = templates()
+        .suits
+        .iter()
+        .filter(|(suit, _)| matches!(suit, Suit::Heart | Suit::Diamond) == red)
+        .cloned()
+        .collect();
+
+    let (rank, rank_match) = best(&templates().ranks, &features(&crop(&rank), RANK_SIZE));
+    let (suit, suit_match) = best(&suits, &features(&crop(&suit), SUIT_SIZE));
+    Some((Card(rank, suit), rank_match.min(suit_match).clamp(0.0, 1.0)))
+}
+
+#[test]
+fn test_runs() {
+    let marks = [false, true, true, false, true, false, true, true, true];
+    assert_eq!(runs(&marks, 1), vec![1..3, 4..5, 6..9]);
+    assert_eq!(runs(&marks, 2), vec![1..3, 6..9]);
+}
+
+// Every screenshot in tests/screenshots/, against the hand in its name.
+// They're drawn the same way as the crops, so this guards the pipeline
+// rather than its accuracy on real captures.
+#[test]
+fn test_corpus() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/screenshots");
+    let mut shots: Vec<_> = std::fs::read_dir(&corpus).unwrap().map(|entry| entry.unwrap().path()).collect();
+    shots.sort();
+    assert!(shots.len() >= 8);
+
+    for shot in shots {
+        let name = shot.file_stem().unwrap().to_str().unwrap();
+        let expected = crate::parse_input::parse(&name.replace('-', " ")).unwrap();
+        let recognised = open(&shot).unwrap_or_else(|error| panic!("{name}: {error}"));
+        assert_eq!(recognised.full_hand, expected, "{name}");
+        // The smallest cards are under 70 pixels wide, with ranks 11 high.
+        assert!(
+            recognised.confidence.iter().all(|&confidence| confidence > 0.75),
+            "{name}: {:?}",
+            recognised.confidence
+        );
+    }
+}
+
+#[test]
+fn test_not_a_hand() {
+    let felt = DynamicImage::ImageRgb8(RgbImage::from_pixel(640, 360, Rgb([20, 80, 40])));
+    assert!(matches!(recognise(&felt), Err(Error::Cards(0))));
+
+    // All card stock, right up to the edges, and too wide to be a card.
+    let blank = DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 48, Rgb([255, 255, 255])));
+    assert!(matches!(recognise(&blank), Err(Error::Cards(0))));
+}
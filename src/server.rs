@@ -0,0 +1,547 @@
+use crate::Card;
+use crate::Deck;
+use crate::FullHand;
+use crate::all_deals;
+use crate::classify;
+use crate::hold;
+use crate::parse_input;
+use crate::paytable;
+use crate::paytable::Paytable;
+use crate::strategy;
+use crate::trainer;
+use crate::tree_check;
+use crate::tree_check::PartialScore;
+
+#[cfg(feature = "io")]
+use std::io;
+#[cfg(feature = "io")]
+use std::net::SocketAddr;
+#[cfg(feature = "io")]
+use std::panic;
+#[cfg(feature = "io")]
+use std::panic::AssertUnwindSafe;
+#[cfg(feature = "io")]
+use std::sync::Arc;
+#[cfg(feature = "io")]
+use std::sync::Mutex;
+use std::sync::OnceLock;
+#[cfg(feature = "io")]
+use std::sync::mpsc;
+#[cfg(feature = "io")]
+use std::thread;
+
+use rational::Rational;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+
+#[cfg(feature = "io")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    // 127.0.0.1 keeps it to this machine; 0.0.0.0 lets a phone on the same
+    // network reach the page, though only this machine gets /rtp.
+    pub host: String,
+    pub port: u16,
+    // Threads handling requests, so how many are handled at once.
+    pub workers: usize,
+    // Requests left waiting for a worker; any more are turned away with a
+    // 503 rather than piling up.
+    pub queue: usize,
+}
+
+#[cfg(feature = "io")]
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            host: "127.0.0.1".to_string(),
+            port: 7878,
+            workers: 4,
+            queue: 16,
+        }
+    }
+}
+
+// A local JSON API over the solver, for tools that would rather not spawn
+// the CLI for every hand:
+//
+//   POST /analyze    {"hand", "multiplier"?, "dead"?, "top"?, "odds"?}
+//   POST /regret     {"hand", "hold", "multiplier"?, "dead"?}
+//   POST /double-up  {"card", "winnings"}
+//   GET  /paytable
+//   POST /rtp        {"multiplier"?, "pays"?}
+//
+// Holds are slot positions 1-5 and cards are text parse_input reads, as in
+// the history log. Expected scores are per chip, with the exact chips
+// alongside as a fraction. With "odds": true each hold also carries the
+// chance of each category. Errors come back as {"error": "..."}.
+//
+// The paytable is the game's own and fixed: it's what /paytable reports
+// and what every hold is solved for. "pays" ("2,3,4,8,10,15,50,100,500")
+// only prices another one for that /rtp.
+//
+// Nothing here changes any state, but /rtp ties a worker up for seconds,
+// so over HTTP it's only answered to clients on this machine.
+#[derive(Default)]
+pub struct Api {
+    // Built on the first /rtp, as they take a few seconds.
+    table: OnceLock<(all_deals::Table, Vec<(FullHand, u32)>)>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Hand {
+    pub(crate) hand: String,
+    #[serde(default = "one")]
+    pub(crate) multiplier: i32,
+    // Cards known to be out of the deck besides the hand.
+    #[serde(default)]
+    pub(crate) dead: Vec<String>,
+    pub(crate) top: Option<usize>,
+    pub(crate) hold: Option<Vec<usize>>,
+    #[serde(default)]
+    pub(crate) odds: bool,
+}
+
+#[derive(Deserialize)]
+struct DoubleUp {
+    card: String,
+    winnings: i32,
+}
+
+#[derive(Deserialize)]
+struct Stake {
+    #[serde(default = "one")]
+    multiplier: i32,
+    pays: Option<String>,
+}
+
+fn one() -> i32 {
+    1
+}
+
+type Response = Result<Value, (u16, String)>;
+
+pub(crate) fn bad_request(message: &str) -> (u16, String) {
+    (400, message.to_string())
+}
+
+impl Api {
+    // The status and body for one request.
+    pub fn handle(&self, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let result = match (method, path) {
+            ("POST", "/analyze") => parse_body(body).and_then(|hand| self.analyze(&hand)),
+            ("POST", "/regret") => parse_body(body).and_then(|hand| self.regret(&hand)),
+            ("POST", "/double-up") => parse_body(body).and_then(|bet| self.double_up(&bet)),
+            ("GET", "/paytable") => Ok(self.paytable()),
+            ("POST", "/rtp") => parse_body(body).and_then(|stake| self.rtp(&stake)),
+            (_, "/analyze" | "/regret" | "/double-up" | "/paytable" | "/rtp") => {
+                Err((405, format!("{method} not allowed on {path}")))
+            }
+            _ => Err((404, format!("No such endpoint {path}"))),
+        };
+        match result {
+            Ok(value) => (200, value),
+            Err((status, error)) => (status, json!({ "error": error })),
+        }
+    }
+
+    fn analyze(&self, request: &Hand) -> Response {
+        let (full_hand, deck) = deal(request)?;
+        let ranked = tree_check::ranked_swap_values(&full_hand, &deck, request.multiplier);
+        let holds: Vec<Value> = ranked
+            .iter()
+            .take(request.top.unwrap_or(ranked.len()))
+            .map(|(keep, value)| {
+                let mut hold = hold_json(&full_hand, full_hand.mask(keep), value, request.multiplier);
+                if request.odds {
+                    hold["odds"] = odds(&tree_check::check(keep, &deck));
+                }
+                hold
+            })
+            .collect();
+        Ok(json!({
+            "hand": text(&full_hand.cards()),
+            "multiplier": request.multiplier,
+            "holds": holds,
+        }))
+    }
+
+    fn regret(&self, request: &Hand) -> Response {
+        let (full_hand, deck) = deal(request)?;
+        let held = request.hold.as_ref().ok_or_else(|| bad_request("No hold given"))?;
+        let mask = hold::mask(held).map_err(|()| bad_request("Hold positions are 1-5"))?;
+        let keep = full_hand.keep(mask);
+        let regret = trainer::regret(&full_hand, &deck, request.multiplier, &keep);
+        let grade = &regret.grade;
+        let per_chip = |value: Rational| value.decimal_value() / request.multiplier as f64;
+        Ok(json!({
+            "hand": text(&full_hand.cards()),
+            "multiplier": request.multiplier,
+            "hold": hold::positions(grade.chosen),
+            "expected": per_chip(grade.chosen_value),
+            "rank": grade.rank,
+            "correct": grade.correct(),
+            "ev_lost": per_chip(grade.ev_lost()),
+            "best": {
+                "hold": hold::positions(grade.best),
+                "cards": text(&full_hand.keep(grade.best)),
+                "class": grade.best_class.to_string(),
+                "expected": per_chip(grade.best_value),
+            },
+            "odds": {
+                "held": odds(&regret.chosen),
+                "best": odds(&regret.best),
+            },
+        }))
+    }
+
+    fn double_up(&self, request: &DoubleUp) -> Response {
+        let shown = parse_input::parse_cards(&request.card)
+            .ok()
+            .and_then(|cards| (cards.len() == 1).then(|| cards[0].clone()))
+            .ok_or_else(|| bad_request("Give the one card shown"))?;
+        if request.winnings <= 0 {
+            return Err(bad_request("Nothing to double"));
+        }
+        if request.winnings > crate::MAX_MULTIPLIER {
+            return Err(bad_request("Winnings over the cap"));
+        }
+        let (guess, wins) = strategy::best_guess(&shown);
+        let take = strategy::Strategy::double_up(&mut strategy::Optimal, request.winnings, &shown);
+        Ok(json!({
+            "card": shown.to_string(),
+            "guess": format!("{guess:?}").to_lowercase(),
+            "wins": wins,
+            "of": 51,
+            "probability": wins as f64 / 51.0,
+            "take": take.is_some(),
+        }))
+    }
+
+    fn paytable(&self) -> Value {
+        json!({ "pays": Paytable::default().to_string() })
+    }
+
+    fn rtp(&self, request: &Stake) -> Response {
+        if !(1..=crate::MAX_MULTIPLIER).contains(&request.multiplier) {
+            return Err(bad_request("Multiplier out of range"));
+        }
+        let paytable: Paytable = match &request.pays {
+            Some(pays) => pays
+                .parse()
+                .map_err(|()| bad_request("A paytable is nine pays, pair to royal flush"))?,
+            None => Paytable::default(),
+        };
+        let (table, classes) = self
+            .table
+            .get_or_init(|| (all_deals::Table::new(), all_deals::deal_classes()));
+        let result = paytable::rtp(table, classes, &paytable, request.multiplier);
+        let shares: Vec<Value> = result
+            .shares
+            .iter()
+            .map(|share| {
+                json!({
+                    "category": share.category.map_or("nothing".to_string(), |score| score.to_string()),
+                    "frequency": share.frequency.decimal_value(),
+                    "contribution": share.contribution.decimal_value(),
+                })
+            })
+            .collect();
+        Ok(json!({
+            "pays": paytable.to_string(),
+            "multiplier": request.multiplier,
+            "rtp": result.rtp.decimal_value(),
+            "exact": result.rtp.to_string(),
+            "shares": shares,
+        }))
+    }
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, (u16, String)> {
+    serde_json::from_str(body).map_err(|error| (400, error.to_string()))
+}
+
+// One hold and its expected score, as /analyze lists them.
+pub(crate) fn hold_json(full_hand: &FullHand, mask: usize, value: &Rational, multiplier: i32) -> Value {
+    let keep = full_hand.keep(mask);
+    json!({
+        "hold": hold::positions(mask),
+        "cards": text(&keep),
+        "class": classify::classify(full_hand, &keep).to_string(),
+        "expected": value.decimal_value() / multiplier as f64,
+        "exact": value.to_string(),
+    })
+}
+
+pub(crate) fn deal(request: &Hand) -> Result<(FullHand, Deck), (u16, String)> {
+    let full_hand = parse_input::parse(&request.hand).map_err(|()| bad_request("Not a hand"))?;
+    if !(1..=crate::MAX_MULTIPLIER).contains(&request.multiplier) {
+        return Err(bad_request("Multiplier out of range"));
+    }
+    let mut deck: Deck = (&full_hand).into();
+    for cards in &request.dead {
+        let cards = parse_input::parse_cards(cards).map_err(|()| bad_request("Not a dead card"))?;
+        for card in cards {
+            deck = deck.take_card(&card);
+        }
+    }
+    Ok((full_hand, deck))
+}
+
+pub(crate) fn text<C: std::borrow::Borrow<Card>>(cards: &[C]) -> String {
+    cards.iter().map(|card| card.borrow().to_string()).collect::<Vec<_>>().join(" ")
+}
+
+// Chance of each category, by name, and of nothing.
+pub(crate) fn odds(outcomes: &[PartialScore]) -> Value {
+    let mut odds = serde_json::Map::new();
+    let mut nothing = Rational::one();
+    for outcome in outcomes {
+        odds.insert(outcome.category().to_string(), outcome.probability().decimal_value().into());
+        nothing -= outcome.probability();
+    }
+    odds.insert("nothing".to_string(), nothing.decimal_value().into());
+    Value::Object(odds)
+}
+
+#[cfg(feature = "io")]
+pub struct Server {
+    http: tiny_http::Server,
+    workers: usize,
+    queue: usize,
+}
+
+#[cfg(feature = "io")]
+impl Server {
+    // Port 0 picks a free one.
+    pub fn bind(config: &Config) -> io::Result<Self> {
+        let http = tiny_http::Server::http((config.host.as_str(), config.port)).map_err(io::Error::other)?;
+        Ok(Server {
+            http,
+            workers: config.workers.max(1),
+            queue: config.queue,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.http.server_addr().to_ip().unwrap()
+    }
+
+    // Serves until the process ends. This thread takes the requests and
+    // hands them to the workers through a queue of `queue` places.
+    pub fn run(self, api: Api) {
+        dispatch(
+            self.http.incoming_requests(),
+            self.workers,
+            self.queue,
+            move |request| respond(&api, request),
+            |request| send(request, 503, &json!({ "error": "Busy, try again" })),
+        );
+    }
+}
+
+// Hands each of `requests` to one of `workers` threads running `handle`,
+// through a queue of `queue` places; `busy` gets any that find it full.
+#[cfg(feature = "io")]
+fn dispatch<R: Send + 'static>(
+    requests: impl Iterator<Item = R>,
+    workers: usize,
+    queue: usize,
+    handle: impl Fn(R) + Send + Sync + 'static,
+    busy: impl Fn(R),
+) {
+    let handle = Arc::new(handle);
+    let (sender, receiver) = mpsc::sync_channel(queue);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let receiver = Arc::clone(&receiver);
+        let handle = Arc::clone(&handle);
+        thread::spawn(move || {
+            loop {
+                let Ok(request) = receiver.lock().unwrap().recv() else {
+                    break;
+                };
+                handle(request);
+            }
+        });
+    }
+
+    for request in requests {
+        if let Err(mpsc::TrySendError::Full(request)) = sender.try_send(request) {
+            busy(request);
+        }
+    }
+}
+
+// The web page, which drives /analyze.
+#[cfg(feature = "io")]
+const INDEX: &str = include_str!("web/index.html");
+
+#[cfg(feature = "io")]
+fn respond(api: &Api, mut request: tiny_http::Request) {
+    if *request.method() == tiny_http::Method::Get && matches!(request.url(), "/" | "/index.html") {
+        let header = tiny_http::Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+        let _ = request.respond(tiny_http::Response::from_string(INDEX).with_header(header));
+        return;
+    }
+
+    let mut body = String::new();
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    let (status, value) = match request.as_reader().read_to_string(&mut body) {
+        Ok(_) if !served_to(request.remote_addr(), &path) => {
+            (403, json!({ "error": format!("{path} is only served to this machine") }))
+        }
+        Ok(_) => {
+            // A bug in one request shouldn't take the worker down with it.
+            panic::catch_unwind(AssertUnwindSafe(|| api.handle(request.method().as_str(), &path, &body)))
+                .unwrap_or_else(|_| (500, json!({ "error": "Internal error" })))
+        }
+        Err(_) => (400, json!({ "error": "Body isn't UTF-8" })),
+    };
+    send(request, status, &value);
+}
+
+// Whether `path` is answered to a client at `addr`.
+#[cfg(feature = "io")]
+fn served_to(addr: Option<&SocketAddr>, path: &str) -> bool {
+    path != "/rtp" || addr.is_some_and(|addr| addr.ip().is_loopback())
+}
+
+#[cfg(feature = "io")]
+fn send(request: tiny_http::Request, status: u16, value: &Value) {
+    let header = tiny_http::Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = tiny_http::Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    // The client hanging up early is its own problem.
+    let _ = request.respond(response);
+}
+
+#[test]
+fn test_api() {
+    let api = Api::default();
+
+    let (status, analysis) = api.handle(
+        "POST",
+        "/analyze",
+        r#"{"hand": "KH 8D 3D 4D 6D", "multiplier": 2, "top": 3, "odds": true}"#,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(analysis["holds"].as_array().unwrap().len(), 3);
+    assert!(analysis["holds"][0]["odds"]["flush"].as_f64().unwrap() > 0.0);
+    assert_eq!(analysis["holds"][0]["hold"], json!([2, 3, 4, 5]));
+    assert_eq!(analysis["holds"][0]["cards"], "8D 3D 4D 6D");
+
+    let (status, regret) = api.handle("POST", "/regret", r#"{"hand": "KH 8D 3D 4D 6D", "hold": [1]}"#);
+    assert_eq!(status, 200);
+    assert_eq!(regret["correct"], false);
+    assert_eq!(regret["best"]["hold"], json!([2, 3, 4, 5]));
+    let odds = regret["odds"]["best"].as_object().unwrap();
+    let total: f64 = odds.values().map(|p| p.as_f64().unwrap()).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+
+    let (status, double) = api.handle("POST", "/double-up", r#"{"card": "3S", "winnings": 10}"#);
+    assert_eq!(status, 200);
+    assert_eq!(double["guess"], "high");
+    assert_eq!(double["wins"], 40);
+    assert_eq!(double["take"], true);
+    assert_eq!(api.handle("POST", "/double-up", r#"{"card": "3S", "winnings": 10000}"#).0, 400);
+
+    assert_eq!(api.handle("GET", "/paytable", "").1["pays"], "2,3,4,8,10,15,50,100,500");
+    assert_eq!(api.handle("PUT", "/paytable", r#"{"pays": "1,2,3,5,7,10,40,80,400"}"#).0, 405);
+    assert_eq!(api.handle("POST", "/rtp", r#"{"pays": "-1,2,3,5,7,10,40,80,400"}"#).0, 400);
+
+    assert_eq!(api.handle("POST", "/analyze", r#"{"hand": "KH KH"}"#).0, 400);
+    assert_eq!(api.handle("POST", "/rtp", r#"{"multiplier": 0}"#).0, 400);
+    assert_eq!(api.handle("POST", "/rtp", r#"{"multiplier": 10000}"#).0, 400);
+    assert_eq!(api.handle("POST", "/analyze", "not json").0, 400);
+    assert_eq!(api.handle("GET", "/analyze", "").0, 405);
+    assert_eq!(api.handle("GET", "/nowhere", "").0, 404);
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn test_server() {
+    use std::io::Read;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    let server = Server::bind(&Config {
+        port: 0,
+        workers: 2,
+        ..Config::default()
+    })
+    .unwrap();
+    let addr = server.addr();
+    thread::spawn(move || server.run(Api::default()));
+
+    let body = r#"{"card": "KD", "winnings": 5}"#;
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "POST /double-up HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains("application/json"));
+    let (_, json) = response.split_once("\r\n\r\n").unwrap();
+    let json: Value = serde_json::from_str(json).unwrap();
+    assert_eq!(json["guess"], "low");
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    let mut page = String::new();
+    stream.read_to_string(&mut page).unwrap();
+    assert!(page.starts_with("HTTP/1.1 200"));
+    assert!(page.contains("text/html"));
+    assert!(page.contains("fetch(\"/analyze\""));
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn test_served_to() {
+    let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+    let phone: SocketAddr = "192.168.1.20:50000".parse().unwrap();
+    assert!(served_to(Some(&local), "/rtp"));
+    assert!(!served_to(Some(&phone), "/rtp"));
+    assert!(!served_to(None, "/rtp"));
+    assert!(served_to(Some(&phone), "/analyze"));
+    assert!(served_to(Some(&phone), "/paytable"));
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn test_busy() {
+    let (requests, incoming) = mpsc::channel();
+    let (started, start) = mpsc::channel();
+    let (finish, finished) = mpsc::channel::<()>();
+    let finished = Mutex::new(finished);
+    let (turned_away, busy) = mpsc::channel();
+    thread::spawn(move || {
+        dispatch(
+            incoming.into_iter(),
+            1,
+            1,
+            move |request| {
+                started.send(request).unwrap();
+                finished.lock().unwrap().recv().unwrap();
+            },
+            move |request| turned_away.send(request).unwrap(),
+        )
+    });
+
+    // The one worker holds the first request until told to finish it, so
+    // the second waits in the queue and the third finds no room.
+    requests.send(1).unwrap();
+    assert_eq!(start.recv(), Ok(1));
+    requests.send(2).unwrap();
+    requests.send(3).unwrap();
+    assert_eq!(busy.recv(), Ok(3));
+
+    finish.send(()).unwrap();
+    assert_eq!(start.recv(), Ok(2));
+    finish.send(()).unwrap();
+}
@@ -0,0 +1,160 @@
+use crate::Deck;
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::MAX_MULTIPLIER;
+use crate::NEW_DECK;
+use crate::check;
+use crate::paytable::Paytable;
+use crate::strategy::Context;
+use crate::strategy::Guess;
+use crate::strategy::Strategy;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub rounds: u64,
+    pub wagered: i64,
+    pub returned: i64,
+    // Net chips after each round, starting from 0.
+    pub trajectory: Vec<i64>,
+    // Sample variance of a single round's net result.
+    pub variance: f64,
+}
+
+impl Report {
+    pub fn return_to_player(&self) -> f64 {
+        self.returned as f64 / self.wagered as f64
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+// Plays `rounds` full rounds at a stake of `multiplier` chips each: deal
+// five from a freshly shuffled deck, hold, draw the replacements from the
+// top of the same deck, pay `score * multiplier` (capped), then offer
+// double-up while the player keeps winning.
+//
+// Deals come from one RNG and double-up cards from another, both derived
+// from `seed`, so every strategy run with the same seed sees exactly the
+// same deals and draws -- differences in the reports are down to play.
+//
+// Double-up shows a card from a fresh deck and the player guesses whether
+// the next is higher or lower (Ace low); a tie loses.
+pub fn simulate<S: Strategy + ?Sized>(
+    strategy: &mut S,
+    rounds: u64,
+    multiplier: i32,
+    seed: u64,
+) -> Report {
+    let mut deal_rng = StdRng::seed_from_u64(seed);
+    let mut double_up_rng = StdRng::seed_from_u64(seed.wrapping_add(1));
+
+    let mut report = Report {
+        rounds,
+        wagered: 0,
+        returned: 0,
+        trajectory: Vec::with_capacity(rounds as usize),
+        variance: 0.0,
+    };
+    let mut bankroll = 0;
+    let mut mean = 0.0;
+    let mut m2 = 0.0;
+
+    for n in 1..=rounds {
+        let payout = play_round(strategy, multiplier, &mut deal_rng, &mut double_up_rng);
+        let net = (payout - multiplier) as i64;
+
+        report.wagered += multiplier as i64;
+        report.returned += payout as i64;
+        bankroll += net;
+        report.trajectory.push(bankroll);
+
+        let delta = net as f64 - mean;
+        mean += delta / n as f64;
+        m2 += delta * (net as f64 - mean);
+    }
+
+    if rounds > 1 {
+        report.variance = m2 / (rounds - 1) as f64;
+    }
+    report
+}
+
+fn play_round<S: Strategy + ?Sized>(
+    strategy: &mut S,
+    multiplier: i32,
+    deal_rng: &mut StdRng,
+    double_up_rng: &mut StdRng,
+) -> i32 {
+    let mut cards = NEW_DECK.to_vec();
+    cards.shuffle(deal_rng);
+
+    let full_hand = FullHand(
+        cards[0].clone(),
+        cards[1].clone(),
+        cards[2].clone(),
+        cards[3].clone(),
+        cards[4].clone(),
+    );
+    let deck = (&full_hand).into();
+    let context = Context {
+        multiplier,
+        deck: &deck,
+    };
+    let keep = strategy.hold(&full_hand, &context);
+    assert!(keep.len() <= HAND_SIZE);
+
+    let mut full = [&cards[HAND_SIZE]; HAND_SIZE];
+    full[..keep.len()].copy_from_slice(&keep);
+    for (slot, card) in full[keep.len()..].iter_mut().zip(&cards[HAND_SIZE..]) {
+        *slot = card;
+    }
+
+    let mut winnings = check::check(&full).map_or(0, |score| Paytable::default().payout(score, multiplier));
+
+    while winnings > 0 && winnings < MAX_MULTIPLIER {
+        let deck = Deck::new();
+        let mut drawn = deck.sample(2, double_up_rng);
+        let (shown, next) = (drawn.next().unwrap(), drawn.next().unwrap());
+
+        let Some(guess) = strategy.double_up(winnings, shown) else {
+            break;
+        };
+        let won = match guess {
+            Guess::High => next.rank() > shown.rank(),
+            Guess::Low => next.rank() < shown.rank(),
+        };
+        winnings = if won {
+            std::cmp::min(winnings * 2, MAX_MULTIPLIER)
+        } else {
+            0
+        };
+    }
+
+    winnings
+}
+
+#[test]
+fn test_simulate() {
+    use crate::strategy::{KeepPairs, Optimal, Random};
+
+    let a = simulate(&mut KeepPairs, 2000, 1, 3);
+    let b = simulate(&mut KeepPairs, 2000, 1, 3);
+    assert_eq!(a, b);
+
+    assert_eq!(a.trajectory.len(), 2000);
+    assert_eq!(a.wagered, 2000);
+    assert_eq!(*a.trajectory.last().unwrap(), a.returned - a.wagered);
+
+    // Same deals, so keeping pairs beats throwing cards away at random.
+    let random = simulate(&mut Random::new(3), 2000, 1, 3);
+    assert!(a.return_to_player() > random.return_to_player());
+
+    let optimal = simulate(&mut Optimal, 2, 1, 3);
+    assert_eq!(optimal.trajectory.len(), 2);
+}
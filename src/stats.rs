@@ -0,0 +1,121 @@
+// Distribution functions for the significance tests, to the accuracy a
+// p-value needs.
+
+// Abramowitz and Stegun 26.2.17, good to 7.5e-8.
+pub fn normal_cdf(z: f64) -> f64 {
+    const P: f64 = 0.231_641_9;
+    const B: [f64; 5] = [
+        0.319_381_530,
+        -0.356_563_782,
+        1.781_477_937,
+        -1.821_255_978,
+        1.330_274_429,
+    ];
+
+    let t = 1.0 / (1.0 + P * z.abs());
+    let poly = B.iter().rev().fold(0.0, |sum, b| (sum + b) * t);
+    let tail = (-z * z / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt() * poly;
+    if z >= 0.0 { 1.0 - tail } else { tail }
+}
+
+// Chance of a statistic at least as far from 0 as `z`, either way.
+pub fn two_sided_p(z: f64) -> f64 {
+    2.0 * (1.0 - normal_cdf(z.abs()))
+}
+
+// Chance of a chi-square statistic at least this large with `dof` degrees
+// of freedom: the regularised upper incomplete gamma Q(dof / 2, x / 2).
+pub fn chi_square_p(statistic: f64, dof: usize) -> f64 {
+    let a = dof as f64 / 2.0;
+    let x = statistic / 2.0;
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefix = (a * x.ln() - x - ln_gamma(a)).exp();
+
+    if x < a + 1.0 {
+        // The series for P converges quickly here.
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..1000 {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term < sum * 1e-15 {
+                break;
+            }
+        }
+        1.0 - sum * prefix
+    } else {
+        // And Lentz's continued fraction for Q everywhere else.
+        const TINY: f64 = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for n in 1..1000 {
+            let an = -(n as f64) * (n as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        prefix * h
+    }
+}
+
+// Lanczos (g = 7, n = 9), good to about 15 digits for positive x.
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const C: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    let x = x - 1.0;
+    let t = x + G + 0.5;
+    let sum = C[1..]
+        .iter()
+        .enumerate()
+        .fold(C[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[test]
+fn test_normal_cdf() {
+    assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+    assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+    assert!((normal_cdf(-1.0) - 0.158_655).abs() < 1e-6);
+    assert!((two_sided_p(1.96) - 0.05).abs() < 1e-4);
+}
+
+#[test]
+fn test_chi_square_p() {
+    assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-12);
+    assert!((ln_gamma(0.5) - std::f64::consts::PI.sqrt().ln()).abs() < 1e-12);
+
+    // Critical values at 5% from the usual tables.
+    assert!((chi_square_p(3.841, 1) - 0.05).abs() < 1e-4);
+    assert!((chi_square_p(7.815, 3) - 0.05).abs() < 1e-4);
+    assert!((chi_square_p(21.026, 12) - 0.05).abs() < 1e-4);
+    // With two degrees of freedom it's just exp(-x / 2).
+    assert!((chi_square_p(1.0, 2) - (-0.5f64).exp()).abs() < 1e-12);
+    assert_eq!(chi_square_p(0.0, 4), 1.0);
+}
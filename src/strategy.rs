@@ -0,0 +1,564 @@
+use crate::Card;
+use crate::Deck;
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::MAX_MULTIPLIER;
+use crate::Score;
+use crate::all_deals;
+use crate::check;
+use crate::tree_check;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use itertools::Itertools;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rational::Rational;
+
+// What a strategy gets to see besides the hand itself.
+pub struct Context<'a> {
+    pub multiplier: i32,
+    // The cards that can still be drawn.
+    pub deck: &'a Deck,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Guess {
+    High,
+    Low,
+}
+
+pub trait Strategy {
+    fn name(&self) -> &str;
+
+    // Which of the dealt cards to keep; everything else is swapped.
+    fn hold<'a>(&mut self, full_hand: &'a FullHand, context: &Context) -> Vec<&'a Card>;
+
+    // Offered after every win, and again after every won double-up: None
+    // banks `winnings`, Some(guess) bets them all on the next card.
+    fn double_up(&mut self, _winnings: i32, _shown: &Card) -> Option<Guess> {
+        None
+    }
+}
+
+// Keeps the best hold from `tree_check::ranked_swap_values`, and doubles up
+// only when the better guess is favourable for the chips actually at stake
+// (the cap can make even a good guess not worth it).
+pub struct Optimal;
+
+impl Strategy for Optimal {
+    fn name(&self) -> &str {
+        "optimal"
+    }
+
+    fn hold<'a>(&mut self, full_hand: &'a FullHand, context: &Context) -> Vec<&'a Card> {
+        tree_check::ranked_swap_values(full_hand, context.deck, context.multiplier)
+            .swap_remove(0)
+            .0
+    }
+
+    fn double_up(&mut self, winnings: i32, shown: &Card) -> Option<Guess> {
+        let (guess, wins) = best_guess(shown);
+        // In i64 so no stake can overflow.
+        let winnings = winnings as i64;
+        let doubled = std::cmp::min(winnings * 2, MAX_MULTIPLIER as i64);
+        // wins / 51 * doubled > winnings, kept in integers.
+        (wins as i64 * doubled > 51 * winnings).then_some(guess)
+    }
+}
+
+// The better guess against `shown`, and how many of the 51 unseen cards
+// win it. Ties lose.
+pub fn best_guess(shown: &Card) -> (Guess, i32) {
+    let higher = 4 * (13 - shown.rank() as i32);
+    let lower = 4 * (shown.rank() as i32 - 1);
+    if higher >= lower {
+        (Guess::High, higher)
+    } else {
+        (Guess::Low, lower)
+    }
+}
+
+// Keeps any cards that share a rank (pairs, trips, quads) and swaps the
+// rest, ignoring straights and flushes altogether.
+pub struct KeepPairs;
+
+impl Strategy for KeepPairs {
+    fn name(&self) -> &str {
+        "keep pairs"
+    }
+
+    fn hold<'a>(&mut self, full_hand: &'a FullHand, _context: &Context) -> Vec<&'a Card> {
+        let cards = full_hand.cards();
+        cards
+            .iter()
+            .filter(|card| cards.iter().filter(|other| other.rank() == card.rank()).count() > 1)
+            .copied()
+            .collect()
+    }
+}
+
+// Keeps each card with probability 1/2 and takes a coin-flip double-up.
+pub struct Random(pub StdRng);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Strategy for Random {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn hold<'a>(&mut self, full_hand: &'a FullHand, _context: &Context) -> Vec<&'a Card> {
+        full_hand
+            .cards()
+            .into_iter()
+            .filter(|_| self.0.random_bool(0.5))
+            .collect()
+    }
+
+    fn double_up(&mut self, _winnings: i32, _shown: &Card) -> Option<Guess> {
+        match self.0.random_range(0..3) {
+            0 => Some(Guess::High),
+            1 => Some(Guess::Low),
+            _ => None,
+        }
+    }
+}
+
+// The shape of a hold, as a human would name it. Made hands keep just the
+// cards that make them (the pair, not the kickers); draws keep `n` cards,
+// at least two: one card on its own fits almost any draw, so it's none.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Pattern {
+    RoyalFlush,
+    StraightFlush,
+    FourOfAKind,
+    FullHouse,
+    Flush,
+    Straight,
+    ThreeOfAKind,
+    TwoPair,
+    Pair,
+    ToRoyal(usize),
+    ToStraightFlush(usize),
+    ToFlush(usize),
+    ToOutsideStraight(usize),
+    ToInsideStraight(usize),
+    ToDoubleInsideStraight(usize),
+    Nothing,
+}
+
+impl Pattern {
+    // Most to least specific, so the first match is the best description.
+    pub fn all() -> Vec<Pattern> {
+        let mut all = vec![
+            Pattern::RoyalFlush,
+            Pattern::StraightFlush,
+            Pattern::FourOfAKind,
+            Pattern::FullHouse,
+            Pattern::Flush,
+            Pattern::Straight,
+            Pattern::ThreeOfAKind,
+            Pattern::TwoPair,
+            Pattern::Pair,
+        ];
+        for n in (2..HAND_SIZE).rev() {
+            all.extend([
+                Pattern::ToRoyal(n),
+                Pattern::ToStraightFlush(n),
+                Pattern::ToFlush(n),
+                Pattern::ToOutsideStraight(n),
+                Pattern::ToInsideStraight(n),
+                Pattern::ToDoubleInsideStraight(n),
+            ]);
+        }
+        all.push(Pattern::Nothing);
+        all
+    }
+
+    pub fn classify(keep: &[&Card]) -> Option<Pattern> {
+        Pattern::all().into_iter().find(|pattern| pattern.matches(keep))
+    }
+
+    pub fn matches(&self, keep: &[&Card]) -> bool {
+        let made = |score: Score| {
+            keep.len() == HAND_SIZE
+                && check::check(&[keep[0], keep[1], keep[2], keep[3], keep[4]]) == Some(score)
+        };
+        let draw = |n: usize| keep.len() == n && (2..HAND_SIZE).contains(&n);
+
+        match *self {
+            Pattern::RoyalFlush => made(Score::RoyalFlush),
+            Pattern::StraightFlush => made(Score::StraightFlush),
+            Pattern::FourOfAKind => keep.len() == 4 && same_rank(keep),
+            Pattern::FullHouse => made(Score::FullHouse),
+            Pattern::Flush => made(Score::Flush),
+            Pattern::Straight => made(Score::Straight),
+            Pattern::ThreeOfAKind => keep.len() == 3 && same_rank(keep),
+            Pattern::TwoPair => {
+                keep.len() == 4 && keep.iter().all(|card| rank_count(keep, card) == 2)
+            }
+            Pattern::Pair => keep.len() == 2 && same_rank(keep),
+            Pattern::ToRoyal(n) => {
+                draw(n)
+                    && same_suit(keep)
+                    && keep.iter().all(|card| card.rank() == 1 || card.rank() >= 10)
+            }
+            Pattern::ToStraightFlush(n) => {
+                draw(n) && same_suit(keep) && straights_containing(keep) > 0
+            }
+            Pattern::ToFlush(n) => draw(n) && same_suit(keep),
+            Pattern::ToOutsideStraight(n) => {
+                draw(n) && straights_containing(keep) > 1 && consecutive(keep)
+            }
+            Pattern::ToInsideStraight(n) => draw(n) && straight_gaps(keep).is_some_and(|gaps| gaps <= 1),
+            Pattern::ToDoubleInsideStraight(n) => draw(n) && straight_gaps(keep).is_some_and(|gaps| gaps > 1),
+            Pattern::Nothing => keep.is_empty(),
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::RoyalFlush => write!(f, "royal flush"),
+            Pattern::StraightFlush => write!(f, "straight flush"),
+            Pattern::FourOfAKind => write!(f, "four of a kind"),
+            Pattern::FullHouse => write!(f, "full house"),
+            Pattern::Flush => write!(f, "flush"),
+            Pattern::Straight => write!(f, "straight"),
+            Pattern::ThreeOfAKind => write!(f, "three of a kind"),
+            Pattern::TwoPair => write!(f, "two pair"),
+            Pattern::Pair => write!(f, "pair"),
+            Pattern::ToRoyal(n) => write!(f, "{n} to a royal"),
+            Pattern::ToStraightFlush(n) => write!(f, "{n} to a straight flush"),
+            Pattern::ToFlush(n) => write!(f, "{n} to a flush"),
+            Pattern::ToOutsideStraight(n) => write!(f, "{n} to an outside straight"),
+            Pattern::ToInsideStraight(n) => write!(f, "{n} to an inside straight"),
+            Pattern::ToDoubleInsideStraight(n) => write!(f, "{n} to a double-inside straight"),
+            Pattern::Nothing => write!(f, "nothing"),
+        }
+    }
+}
+
+// By the name Display gives it, e.g. "4 to a flush".
+impl std::str::FromStr for Pattern {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Pattern::all().into_iter().find(|pattern| pattern.to_string() == name).ok_or(())
+    }
+}
+
+fn same_rank(keep: &[&Card]) -> bool {
+    keep.iter().all(|card| card.rank() == keep[0].rank())
+}
+
+fn same_suit(keep: &[&Card]) -> bool {
+    keep.iter().all(|card| card.suit() == keep[0].suit())
+}
+
+fn rank_count(keep: &[&Card], card: &Card) -> usize {
+    keep.iter().filter(|other| other.rank() == card.rank()).count()
+}
+
+// Rank bits with Ace low (bit 0) and with Ace high (bit 13), or None if
+// `keep` repeats a rank.
+fn rank_masks(keep: &[&Card]) -> Option<(u16, u16)> {
+    let low = keep.iter().fold(0u16, |mask, card| mask | 1 << (card.rank() - 1));
+    if low.count_ones() as usize != keep.len() {
+        return None;
+    }
+    let high = if low & 1 != 0 { (low & !1) | 1 << 13 } else { low };
+    Some((low, high))
+}
+
+// How many of the ten straights (A-5 up to 10-A) `keep` is part of. An
+// outside draw like 5-6-7-8 fits two of them, while A-2-3-4 or a gutshot
+// like 5-6-8-9 only fit one.
+pub(crate) fn straights_containing(keep: &[&Card]) -> usize {
+    let Some((low, high)) = rank_masks(keep) else {
+        return 0;
+    };
+    (0..10)
+        .filter(|start| {
+            let window = 0b1_1111 << start;
+            low & !window == 0 || high & !window == 0
+        })
+        .count()
+}
+
+// The fewest ranks missing from between the cards of a straight `keep` is
+// part of, Ace low or high: 0 for 5-6-7-8 or A-2-3-4, 1 for 5-6-8-9, 2 for
+// 5-7-9. None if it's part of no straight.
+fn straight_gaps(keep: &[&Card]) -> Option<usize> {
+    let (low, high) = rank_masks(keep)?;
+    let gaps = |mask: u16| {
+        let span = 16 - mask.leading_zeros() - mask.trailing_zeros();
+        (span as usize <= HAND_SIZE).then(|| span as usize - keep.len())
+    };
+    [gaps(low), gaps(high)].into_iter().flatten().min()
+}
+
+// No gaps between the ranks, Ace low or high.
+fn consecutive(keep: &[&Card]) -> bool {
+    let Some((low, high)) = rank_masks(keep) else {
+        return false;
+    };
+    let span = |mask: u16| 15 - mask.leading_zeros() - mask.trailing_zeros();
+    keep.is_empty() || span(low) as usize == keep.len() - 1 || span(high) as usize == keep.len() - 1
+}
+
+// An ordered, memorisable rule list: hold the cards for the first pattern
+// the dealt hand contains, or swap everything if none apply.
+pub struct RuleBased {
+    pub rules: Vec<Pattern>,
+}
+
+impl Default for RuleBased {
+    fn default() -> Self {
+        RuleBased {
+            rules: vec![
+                Pattern::RoyalFlush,
+                Pattern::StraightFlush,
+                Pattern::FourOfAKind,
+                Pattern::ToRoyal(4),
+                Pattern::FullHouse,
+                Pattern::Flush,
+                Pattern::Straight,
+                Pattern::ThreeOfAKind,
+                Pattern::ToStraightFlush(4),
+                Pattern::TwoPair,
+                Pattern::Pair,
+                Pattern::ToFlush(4),
+                Pattern::ToRoyal(3),
+                Pattern::ToStraightFlush(3),
+                Pattern::ToOutsideStraight(4),
+                Pattern::ToFlush(3),
+                Pattern::ToRoyal(2),
+                Pattern::ToStraightFlush(2),
+            ],
+        }
+    }
+}
+
+impl Strategy for RuleBased {
+    fn name(&self) -> &str {
+        "rule based"
+    }
+
+    fn hold<'a>(&mut self, full_hand: &'a FullHand, _context: &Context) -> Vec<&'a Card> {
+        let holds: Vec<Vec<&Card>> = full_hand.cards().into_iter().powerset().collect();
+        self.rules
+            .iter()
+            .find_map(|rule| holds.iter().find(|keep| rule.matches(keep)))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+// How much expected score `strategy` gives up on one hand against the best
+// hold from `tree_check::ranked_swap_values`.
+pub fn ev_loss<S: Strategy + ?Sized>(
+    strategy: &mut S,
+    full_hand: &FullHand,
+    context: &Context,
+) -> Rational {
+    let ranked = tree_check::ranked_swap_values(full_hand, context.deck, context.multiplier);
+    let mask = full_hand.mask(&strategy.hold(full_hand, context));
+    let (_, chosen) = ranked
+        .iter()
+        .find(|(keep, _)| full_hand.mask(keep) == mask)
+        .unwrap();
+    ranked[0].1 - *chosen
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Loss {
+    pub deals: u64,
+    // Deals where the strategy's hold scores less than the best one.
+    pub mistakes: u64,
+    // Summed over the deals, so `mean` gives the loss per hand.
+    pub ev_loss: Rational,
+}
+
+impl Loss {
+    pub(crate) fn new() -> Self {
+        Loss {
+            deals: 0,
+            mistakes: 0,
+            ev_loss: Rational::zero(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, deals: u32, ev_loss: Rational) {
+        self.deals += deals as u64;
+        if ev_loss != Rational::zero() {
+            self.mistakes += deals as u64;
+            self.ev_loss += ev_loss * deals;
+        }
+    }
+
+    pub fn mean(&self) -> Rational {
+        self.ev_loss / self.deals
+    }
+}
+
+pub struct Evaluation {
+    pub total: Loss,
+    // Keyed by the pattern of the best hold, None for ones that fit none.
+    pub by_class: BTreeMap<Option<Pattern>, Loss>,
+}
+
+// `ev_loss` over every one of the C(52, 5) possible deals, with a fresh
+// deck. Goes through `all_deals` rather than `tree_check`, which would take
+// hours, and weighs each suit class by the deals it stands for.
+pub fn evaluate<S: Strategy + ?Sized>(
+    strategy: &mut S,
+    table: &all_deals::Table,
+    multiplier: i32,
+) -> Evaluation {
+    let mut evaluation = Evaluation {
+        total: Loss::new(),
+        by_class: BTreeMap::new(),
+    };
+
+    for (full_hand, deals) in all_deals::deal_classes() {
+        let deck = (&full_hand).into();
+        let context = Context {
+            multiplier,
+            deck: &deck,
+        };
+        let ranked = table.ranked_swap_values(&full_hand, multiplier);
+        let mask = full_hand.mask(&strategy.hold(&full_hand, &context));
+        let (_, chosen) = ranked
+            .iter()
+            .find(|(keep, _)| full_hand.mask(keep) == mask)
+            .unwrap();
+        let loss = ranked[0].1 - *chosen;
+
+        evaluation.total.add(deals, loss);
+        evaluation
+            .by_class
+            .entry(Pattern::classify(&ranked[0].0))
+            .or_insert_with(Loss::new)
+            .add(deals, loss);
+    }
+
+    evaluation
+}
+
+#[test]
+fn test_keep_pairs() {
+    use crate::Rank::*;
+    use crate::Suit::*;
+
+    let full_hand = FullHand(
+        Card(Ace, Club),
+        Card(Four, Heart),
+        Card(Ace, Heart),
+        Card(Four, Spade),
+        Card(Nine, Heart),
+    );
+    let deck = (&full_hand).into();
+    let context = Context {
+        multiplier: 1,
+        deck: &deck,
+    };
+
+    assert_eq!(
+        KeepPairs.hold(&full_hand, &context),
+        vec![&full_hand.0, &full_hand.1, &full_hand.2, &full_hand.3]
+    );
+}
+
+#[test]
+fn test_optimal_double_up() {
+    use crate::Rank::*;
+    use crate::Suit::*;
+
+    assert_eq!(Optimal.double_up(10, &Card(Two, Club)), Some(Guess::High));
+    assert_eq!(Optimal.double_up(10, &Card(Queen, Club)), Some(Guess::Low));
+    // 24/51 either way with ties losing, so not worth it.
+    assert_eq!(Optimal.double_up(10, &Card(Seven, Club)), None);
+    // Doubling 9000 only gains 999 chips at the cap.
+    assert_eq!(Optimal.double_up(9000, &Card(Two, Club)), None);
+    assert_eq!(Optimal.double_up(i32::MAX, &Card(Two, Club)), None);
+}
+
+#[test]
+fn test_patterns() {
+    use crate::parse_input::parse;
+
+    let hand = parse("10h Jh Qh Kh 4c").unwrap();
+    assert_eq!(Pattern::classify(&hand.keep(0b01111)), Some(Pattern::ToRoyal(4)));
+    assert_eq!(Pattern::classify(&hand.keep(0b00111)), Some(Pattern::ToRoyal(3)));
+    assert_eq!(Pattern::classify(&hand.keep(0b00001)), None);
+    assert_eq!(Pattern::classify(&hand.keep(0b11111)), None);
+    assert_eq!(Pattern::classify(&hand.keep(0)), Some(Pattern::Nothing));
+
+    let hand = parse("Ah 2c 3h 4s 4d").unwrap();
+    assert_eq!(Pattern::classify(&hand.keep(0b01111)), Some(Pattern::ToInsideStraight(4)));
+    assert_eq!(Pattern::classify(&hand.keep(0b11000)), Some(Pattern::Pair));
+    assert_eq!(Pattern::classify(&hand.keep(0b00101)), Some(Pattern::ToStraightFlush(2)));
+    assert_eq!(Pattern::classify(&hand.keep(0b10011)), Some(Pattern::ToInsideStraight(3)));
+    assert_eq!(Pattern::classify(&hand.keep(0b10001)), Some(Pattern::ToDoubleInsideStraight(2)));
+    assert_eq!(Pattern::classify(&hand.keep(0b11100)), None);
+
+    let hand = parse("Ah Kc Qh Jd 9s").unwrap();
+    assert_eq!(Pattern::classify(&hand.keep(0b01111)), Some(Pattern::ToInsideStraight(4)));
+    assert_eq!(Pattern::classify(&hand.keep(0b10111)), None);
+
+    let hand = parse("5c 6d 7h 8s Kd").unwrap();
+    assert_eq!(Pattern::classify(&hand.keep(0b01111)), Some(Pattern::ToOutsideStraight(4)));
+    let hand = parse("10c Jd Qh Ks 3d").unwrap();
+    assert_eq!(Pattern::classify(&hand.keep(0b01111)), Some(Pattern::ToOutsideStraight(4)));
+}
+
+#[test]
+fn test_rule_based() {
+    use crate::parse_input::parse;
+
+    let mut strategy = RuleBased::default();
+    let check = |strategy: &mut RuleBased, hand: &str, expected: usize| {
+        let full_hand = parse(hand).unwrap();
+        let deck = (&full_hand).into();
+        let context = Context {
+            multiplier: 1,
+            deck: &deck,
+        };
+        assert_eq!(full_hand.mask(&strategy.hold(&full_hand, &context)), expected, "{hand}");
+    };
+
+    check(&mut strategy, "10h Jh Qh Kh Kc", 0b01111);
+    check(&mut strategy, "2h 7h 9h Kh Kc", 0b11000);
+    check(&mut strategy, "2h 7h 9h Kh 3c", 0b01111);
+    check(&mut strategy, "2h 7c 9d Js 4c", 0b10010);
+    // Nothing to go on, so swap the lot.
+    check(&mut strategy, "2h 8c 9d Js 3c", 0);
+}
+
+#[test]
+fn test_ev_loss() {
+    use crate::parse_input::parse;
+
+    let full_hand = parse("KH 8D 3D 4D 6D").unwrap();
+    let deck = (&full_hand).into();
+    let context = Context {
+        multiplier: 1,
+        deck: &deck,
+    };
+    let ranked = tree_check::ranked_swap_values(&full_hand, &deck, 1);
+    let (_, swap_all) = ranked.iter().find(|(keep, _)| keep.is_empty()).unwrap();
+
+    // The best hold is the 4 to a flush (see tree_check::test_best_score),
+    // while with no pair to keep KeepPairs swaps everything.
+    assert_eq!(ev_loss(&mut RuleBased::default(), &full_hand, &context), Rational::zero());
+    assert_eq!(ev_loss(&mut KeepPairs, &full_hand, &context), ranked[0].1 - *swap_all);
+}
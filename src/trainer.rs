@@ -0,0 +1,205 @@
+use crate::Card;
+use crate::Deck;
+use crate::FullHand;
+use crate::classify::HoldClass;
+use crate::classify::classify;
+use crate::strategy::Loss;
+use crate::strategy::Pattern;
+use crate::tree_check;
+use crate::tree_check::PartialScore;
+
+use std::collections::BTreeMap;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rational::Rational;
+
+// How one answer compares with the best hold for the deal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grade {
+    pub chosen: usize,
+    pub best: usize,
+    pub chosen_value: Rational,
+    pub best_value: Rational,
+    // The best value of any hold worth less than the best, so how close a
+    // call the deal was. Equal to `best_value` if every hold ties.
+    pub runner_up_value: Rational,
+    // 1 for the best hold (or one tied with it), up to 32. Holds tied with
+    // the chosen one share its rank.
+    pub rank: usize,
+    pub best_class: HoldClass,
+}
+
+impl Grade {
+    pub fn ev_lost(&self) -> Rational {
+        self.best_value - self.chosen_value
+    }
+
+    pub fn correct(&self) -> bool {
+        self.chosen_value == self.best_value
+    }
+}
+
+// Grades holding the cards of `full_hand` picked out by `mask` (bit i for
+// the i-th card), drawing from `deck`.
+pub fn grade(full_hand: &FullHand, deck: &Deck, multiplier: i32, mask: usize) -> Grade {
+    let ranked = tree_check::ranked_swap_values(full_hand, deck, multiplier);
+
+    let (best_keep, best_value) = &ranked[0];
+    let chosen_value = ranked
+        .iter()
+        .find(|(keep, _)| full_hand.mask(keep) == mask)
+        .map(|(_, value)| *value)
+        .unwrap();
+
+    Grade {
+        chosen: mask,
+        best: full_hand.mask(best_keep),
+        chosen_value,
+        best_value: *best_value,
+        runner_up_value: ranked
+            .iter()
+            .map(|(_, value)| *value)
+            .find(|value| value < best_value)
+            .unwrap_or(*best_value),
+        rank: 1 + ranked.iter().filter(|(_, value)| *value > chosen_value).count(),
+        best_class: classify(full_hand, best_keep),
+    }
+}
+
+// A hold already made, next to the best one: how the draw could turn out
+// for each, category by category (best first, impossible ones left out).
+#[derive(Debug, PartialEq)]
+pub struct Regret {
+    pub grade: Grade,
+    pub chosen: Vec<PartialScore>,
+    pub best: Vec<PartialScore>,
+}
+
+// Grades holding `keep` out of `full_hand`, drawing from `deck`, with the
+// odds behind both it and the best hold.
+pub fn regret(full_hand: &FullHand, deck: &Deck, multiplier: i32, keep: &[&Card]) -> Regret {
+    let grade = grade(full_hand, deck, multiplier, full_hand.mask(keep));
+    Regret {
+        chosen: tree_check::check(keep, deck),
+        best: tree_check::check(&full_hand.keep(grade.best), deck),
+        grade,
+    }
+}
+
+// Running totals for a session, overall and by the pattern of the hold
+// that was best, so weak spots show up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub total: Loss,
+    pub by_pattern: BTreeMap<Pattern, Loss>,
+}
+
+impl Record {
+    pub fn new() -> Self {
+        Record {
+            total: Loss::new(),
+            by_pattern: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, grade: &Grade) {
+        self.total.add(1, grade.ev_lost());
+        self.by_pattern
+            .entry(grade.best_class.pattern)
+            .or_insert_with(Loss::new)
+            .add(1, grade.ev_lost());
+    }
+}
+
+impl Default for Record {
+    fn default() -> Self {
+        Record::new()
+    }
+}
+
+// Deals practice hands from a fresh deck; the same seed deals the same
+// hands, so a session can be replayed.
+pub struct Trainer {
+    pub multiplier: i32,
+    pub record: Record,
+    rng: StdRng,
+    deck: Deck,
+}
+
+impl Trainer {
+    pub fn new(multiplier: i32, seed: u64) -> Self {
+        Trainer {
+            multiplier,
+            record: Record::new(),
+            rng: StdRng::seed_from_u64(seed),
+            deck: Deck::new(),
+        }
+    }
+
+    pub fn deal(&mut self) -> FullHand {
+        self.deck.deal(&mut self.rng)
+    }
+
+    // Grades the answer for `full_hand` and adds it to the record.
+    pub fn answer(&mut self, full_hand: &FullHand, mask: usize) -> Grade {
+        let grade = grade(full_hand, &full_hand.into(), self.multiplier, mask);
+        self.record.add(&grade);
+        grade
+    }
+}
+
+#[test]
+fn test_grade() {
+    use crate::parse_input::parse;
+
+    let full_hand = parse("KH 8D 3D 4D 6D").unwrap();
+    let deck = (&full_hand).into();
+
+    let best = grade(&full_hand, &deck, 1, 0b11110);
+    assert!(best.correct());
+    assert_eq!(best.rank, 1);
+    assert_eq!(best.best, 0b11110);
+    assert_eq!(best.best_class.pattern, Pattern::ToFlush(4));
+    assert_eq!(best.ev_lost(), Rational::zero());
+    assert!(best.runner_up_value < best.best_value);
+
+    let king = grade(&full_hand, &deck, 1, 0b00001);
+    assert!(!king.correct());
+    assert!(king.rank > 1);
+    assert_eq!(king.ev_lost(), king.best_value - king.chosen_value);
+    assert!(king.ev_lost() > Rational::zero());
+}
+
+#[test]
+fn test_trainer() {
+    let mut a = Trainer::new(1, 7);
+    let mut b = Trainer::new(1, 7);
+    let hand = a.deal();
+    assert_eq!(hand, b.deal());
+
+    let best = grade(&hand, &(&hand).into(), 1, 0).best;
+    a.answer(&hand, best);
+    a.answer(&hand, best ^ 0b11111);
+    assert_eq!(a.record.total.deals, 2);
+    assert!(a.record.total.mistakes <= 1);
+    assert_eq!(a.record.by_pattern.values().map(|loss| loss.deals).sum::<u64>(), 2);
+}
+
+#[test]
+fn test_regret() {
+    use crate::Score;
+    use crate::parse_input::parse;
+
+    let full_hand = parse("KH 8D 3D 4D 6D").unwrap();
+    let deck = (&full_hand).into();
+    let keep = full_hand.keep(0b10110);
+    let regret = regret(&full_hand, &deck, 1, &keep);
+
+    assert_eq!(regret.grade.chosen, 0b10110);
+    assert!(regret.grade.rank > 1);
+    assert_eq!(regret.best, tree_check::check(&full_hand.keep(0b11110), &deck));
+    assert_eq!(regret.best[0].category(), Score::Flush);
+    assert_eq!(regret.best[0].probability(), Rational::new(9, 47));
+    assert_eq!(tree_check::expected_score(&regret.chosen, 1), regret.grade.chosen_value);
+}
@@ -0,0 +1,460 @@
+use crate::Card;
+use crate::FullHand;
+use crate::HAND_SIZE;
+use crate::MAX_MULTIPLIER;
+use crate::Rank;
+use crate::Suit;
+use crate::classify;
+use crate::luck;
+use crate::tree_check;
+
+use std::io;
+
+use ratatui::Frame;
+use ratatui::crossterm::event;
+use ratatui::crossterm::event::Event;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::crossterm::event::KeyEventKind;
+use ratatui::crossterm::event::KeyModifiers;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::Paragraph;
+use rational::Rational;
+
+// Holds listed, best first.
+const TOP: usize = 5;
+const BAR_WIDTH: usize = 20;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    // Typing the cards dealt.
+    Deal,
+    // Typing the hand after the draw, to score it for the session.
+    Draw,
+}
+
+// One of the best holds for the deal on screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hold {
+    pub mask: usize,
+    pub value: Rational,
+    // Chance the draw pays anything.
+    pub win: Rational,
+}
+
+// Everything on screen, driven a key at a time. Cards take two keys, rank
+// then suit: A or 1, 2-9, T or 0, J, Q, K, then H, D, C or S.
+pub struct App {
+    pub multiplier: i32,
+    pub mode: Mode,
+    pub session: luck::Session,
+    // The deal, once all five cards are in.
+    pub dealt: Option<FullHand>,
+    pub holds: Vec<Hold>,
+    pub message: String,
+    pub quit: bool,
+    // Of the hand being typed.
+    entered: Vec<Card>,
+    rank: Option<Rank>,
+}
+
+impl App {
+    pub fn new(multiplier: i32) -> Self {
+        App {
+            multiplier,
+            mode: Mode::Deal,
+            session: luck::Session::new(),
+            dealt: None,
+            holds: Vec::new(),
+            message: String::new(),
+            quit: false,
+            entered: Vec::new(),
+            rank: None,
+        }
+    }
+
+    pub fn key(&mut self, key: KeyEvent) {
+        if key.kind == KeyEventKind::Release {
+            return;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Up => self.set_multiplier(self.multiplier + 1),
+            KeyCode::Down => self.set_multiplier(self.multiplier - 1),
+            KeyCode::PageUp => self.set_multiplier(self.multiplier + 10),
+            KeyCode::PageDown => self.set_multiplier(self.multiplier - 10),
+            KeyCode::Backspace => self.undo(),
+            KeyCode::Esc => self.escape(),
+            // Starts from the best hold, so only the cards drawn need typing.
+            KeyCode::Enter if self.mode == Mode::Deal && self.dealt.is_some() => {
+                let dealt = self.dealt.as_ref().unwrap();
+                let best = self.holds.first().map_or(0, |hold| hold.mask);
+                self.entered = dealt.keep(best).into_iter().cloned().collect();
+                self.mode = Mode::Draw;
+                self.rank = None;
+                self.message = "Type the cards drawn".to_string();
+            }
+            KeyCode::Char(c) => match self.rank {
+                None => match rank(c) {
+                    Some(rank) => self.rank = Some(rank),
+                    None => self.message = format!("{c} isn't a rank"),
+                },
+                Some(rank) => match suit(c) {
+                    Some(suit) => self.enter(Card(rank, suit)),
+                    None => self.message = format!("{c} isn't a suit"),
+                },
+            },
+            _ => {}
+        }
+    }
+
+    fn set_multiplier(&mut self, multiplier: i32) {
+        self.multiplier = multiplier.clamp(1, MAX_MULTIPLIER);
+        self.solve();
+    }
+
+    fn undo(&mut self) {
+        if self.rank.take().is_none() && self.entered.pop().is_some() && self.mode == Mode::Deal {
+            self.dealt = None;
+            self.holds.clear();
+        }
+    }
+
+    // Clears what's being typed; from an empty deal, quits.
+    fn escape(&mut self) {
+        match self.mode {
+            Mode::Draw => {
+                self.mode = Mode::Deal;
+                self.entered = self
+                    .dealt
+                    .as_ref()
+                    .map_or(Vec::new(), |dealt| dealt.cards().map(Card::clone).to_vec());
+                self.rank = None;
+                self.message.clear();
+            }
+            Mode::Deal if self.entered.is_empty() && self.rank.is_none() => self.quit = true,
+            Mode::Deal => {
+                self.entered.clear();
+                self.rank = None;
+                self.dealt = None;
+                self.holds.clear();
+            }
+        }
+    }
+
+    fn enter(&mut self, card: Card) {
+        self.rank = None;
+        if self.entered.contains(&card) {
+            self.message = format!("{card} is already in");
+            return;
+        }
+        if self.entered.len() == HAND_SIZE {
+            self.message = "That's five; backspace to change one".to_string();
+            return;
+        }
+        self.entered.push(card);
+        self.message.clear();
+        if self.entered.len() < HAND_SIZE {
+            return;
+        }
+
+        let [a, b, c, d, e] = <[Card; HAND_SIZE]>::try_from(self.entered.clone()).unwrap();
+        let full_hand = FullHand(a, b, c, d, e);
+        match self.mode {
+            Mode::Deal => {
+                self.dealt = Some(full_hand);
+                self.solve();
+            }
+            Mode::Draw => self.record(full_hand),
+        }
+    }
+
+    fn record(&mut self, drawn: FullHand) {
+        let Some(dealt) = self.dealt.take() else {
+            return;
+        };
+        self.message = match self.session.record(&dealt, &(&dealt).into(), &drawn, self.multiplier) {
+            Ok(draw) => format!(
+                "{}: paid {}, expected {:.1}",
+                draw.score.map_or("nothing".to_string(), |score| score.to_string()),
+                draw.payout,
+                draw.expected.decimal_value()
+            ),
+            Err(error) => error.to_string(),
+        };
+        self.mode = Mode::Deal;
+        self.entered.clear();
+        self.holds.clear();
+    }
+
+    fn solve(&mut self) {
+        let Some(full_hand) = &self.dealt else {
+            return;
+        };
+        let deck = full_hand.into();
+        self.holds = tree_check::ranked_swap_values(full_hand, &deck, self.multiplier)
+            .into_iter()
+            .take(TOP)
+            .map(|(keep, value)| {
+                let win = tree_check::check(&keep, &deck)
+                    .iter()
+                    .fold(Rational::zero(), |sum, outcome| sum + outcome.probability());
+                Hold {
+                    mask: full_hand.mask(&keep),
+                    value,
+                    win,
+                }
+            })
+            .collect();
+    }
+}
+
+fn rank(c: char) -> Option<Rank> {
+    match c.to_ascii_lowercase() {
+        'a' | '1' => Some(Rank::Ace),
+        '2'..='9' => Some(Rank::from(c as u8 - b'0')),
+        't' | '0' => Some(Rank::Ten),
+        'j' => Some(Rank::Jack),
+        'q' => Some(Rank::Queen),
+        'k' => Some(Rank::King),
+        _ => None,
+    }
+}
+
+fn suit(c: char) -> Option<Suit> {
+    match c.to_ascii_lowercase() {
+        'h' => Some(Suit::Heart),
+        'd' => Some(Suit::Diamond),
+        'c' => Some(Suit::Club),
+        's' => Some(Suit::Spade),
+        _ => None,
+    }
+}
+
+fn rank_text(rank: Rank) -> String {
+    match rank {
+        Rank::Ace => "A".to_string(),
+        Rank::Jack => "J".to_string(),
+        Rank::Queen => "Q".to_string(),
+        Rank::King => "K".to_string(),
+        rank => (rank as u8).to_string(),
+    }
+}
+
+fn suit_style(suit: Suit) -> Style {
+    match suit {
+        Suit::Heart | Suit::Diamond => Style::new().fg(Color::Red).bg(Color::White),
+        Suit::Club | Suit::Spade => Style::new().fg(Color::Black).bg(Color::White),
+    }
+}
+
+fn symbol(suit: Suit) -> &'static str {
+    match suit {
+        Suit::Club => "♣",
+        Suit::Diamond => "♦",
+        Suit::Heart => "♥",
+        Suit::Spade => "♠",
+    }
+}
+
+// A card face, or the rank typed so far, or a blank.
+fn card_art(card: Option<&Card>, pending: Option<Rank>, held: bool) -> Paragraph<'static> {
+    let (lines, style) = match (card, pending) {
+        (Some(card), _) => {
+            let rank = rank_text(card.0);
+            let suit = symbol(card.1);
+            (
+                vec![
+                    Line::from(rank.clone()),
+                    Line::from(""),
+                    Line::from(suit).centered(),
+                    Line::from(""),
+                    Line::from(rank).right_aligned(),
+                ],
+                suit_style(card.1),
+            )
+        }
+        (None, Some(rank)) => (
+            vec![Line::from(rank_text(rank)), Line::from(""), Line::from("?").centered()],
+            Style::new().fg(Color::Yellow),
+        ),
+        (None, None) => (vec![], Style::new().fg(Color::DarkGray)),
+    };
+    let block = Block::bordered().border_style(Style::new().fg(if held { Color::Yellow } else { Color::Gray }));
+    let block = if held { block.title_bottom(Line::from(" HELD ").centered()) } else { block };
+    Paragraph::new(lines).style(style).block(block)
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [main, side] = Layout::horizontal([Constraint::Min(40), Constraint::Length(30)]).areas(frame.area());
+    let [cards, holds, help] =
+        Layout::vertical([Constraint::Length(9), Constraint::Min(3), Constraint::Length(2)]).areas(main);
+
+    // The cards, with the best hold marked.
+    let title = match app.mode {
+        Mode::Deal => " Dealt ",
+        Mode::Draw => " After the draw ",
+    };
+    let outer = Block::bordered().title(title);
+    let slots = Layout::horizontal([Constraint::Length(9); HAND_SIZE]).spacing(1).areas::<HAND_SIZE>(outer.inner(cards));
+    frame.render_widget(outer, cards);
+    let best = match app.mode {
+        Mode::Deal => app.holds.first().map_or(0, |hold| hold.mask),
+        Mode::Draw => 0,
+    };
+    for (i, slot) in slots.into_iter().enumerate() {
+        let card = app.entered.get(i);
+        let pending = (i == app.entered.len()).then_some(app.rank).flatten();
+        frame.render_widget(card_art(card, pending, best & (1 << i) != 0), slot);
+    }
+
+    // The best holds, each with its chance of paying.
+    let mut lines = Vec::new();
+    if let Some(full_hand) = &app.dealt {
+        for (i, hold) in app.holds.iter().enumerate() {
+            let keep = full_hand.keep(hold.mask);
+            let cards: Vec<String> = keep.iter().map(|card| format!("{card:?}")).collect();
+            let cards = if cards.is_empty() { "nothing".to_string() } else { cards.join(" ") };
+            let style = if i == 0 { Style::new().add_modifier(Modifier::BOLD) } else { Style::new() };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}. {cards:<16}", i + 1), style),
+                Span::raw(format!(" {:>8.4}/chip  ", hold.value.decimal_value() / app.multiplier as f64)),
+                Span::styled(classify::classify(full_hand, &keep).to_string(), Style::new().fg(Color::Gray)),
+            ]));
+            let win = hold.win.decimal_value();
+            let filled = (win * BAR_WIDTH as f64).round() as usize;
+            lines.push(Line::from(vec![
+                Span::raw("   "),
+                Span::styled("█".repeat(filled), Style::new().fg(Color::Green)),
+                Span::styled("░".repeat(BAR_WIDTH - filled), Style::new().fg(Color::DarkGray)),
+                Span::raw(format!(" {:.1}% to win", win * 100.0)),
+            ]));
+        }
+    } else {
+        lines.push(Line::from("Type the five cards dealt"));
+    }
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Best holds ")), holds);
+
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from("cards: rank then suit, e.g. k h   ⌫ undo   Esc clear / quit"),
+            Line::from("↑↓ multiplier (PgUp/PgDn ±10)   Enter: type the cards drawn"),
+        ])
+        .style(Style::new().fg(Color::DarkGray)),
+        help,
+    );
+
+    // Session so far.
+    let session = &app.session;
+    let mut stats = vec![
+        Line::from(format!("Multiplier  ×{}", app.multiplier)).bold(),
+        Line::from(""),
+        Line::from(format!("Hands       {}", session.hands)),
+        Line::from(format!("Won         {}", session.actual)),
+        Line::from(format!("Expected    {:.1}", session.expected.decimal_value())),
+        Line::from(format!("Luck        {:+.1}", session.luck())),
+    ];
+    if let Some(percentile) = session.percentile() {
+        stats.push(Line::from(format!("Percentile  {:.0}", percentile * 100.0)));
+    }
+    stats.push(Line::from(""));
+    stats.push(Line::from(app.message.clone()).style(Style::new().fg(Color::Yellow)));
+    frame.render_widget(
+        Paragraph::new(stats)
+            .wrap(ratatui::widgets::Wrap { trim: true })
+            .block(Block::bordered().title(" Session ")),
+        side,
+    );
+}
+
+// Takes over the terminal until the user quits.
+pub fn run(multiplier: i32) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut app = App::new(multiplier);
+    let result = (|| {
+        while !app.quit {
+            terminal.draw(|frame| draw(frame, &app))?;
+            if let Event::Key(key) = event::read()? {
+                app.key(key);
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+fn type_keys(app: &mut App, keys: &str) {
+    for c in keys.chars().filter(|c| !c.is_whitespace()) {
+        app.key(KeyEvent::from(KeyCode::Char(c)));
+    }
+}
+
+#[test]
+fn test_app() {
+    let mut app = App::new(1);
+    type_keys(&mut app, "kh 8d 3d 4");
+    assert_eq!(app.entered.len(), 3);
+    assert_eq!(app.rank, Some(Rank::Four));
+    app.key(KeyEvent::from(KeyCode::Backspace));
+    assert_eq!(app.rank, None);
+
+    // A repeat is refused; bad keys leave things as they were.
+    type_keys(&mut app, "kh x 4d y 6d");
+    assert_eq!(app.entered.len(), 5);
+    let dealt = app.dealt.clone().unwrap();
+    assert_eq!(dealt, crate::parse_input::parse("KH 8D 3D 4D 6D").unwrap());
+    assert_eq!(app.holds.len(), TOP);
+    assert_eq!(app.holds[0].mask, 0b11110);
+
+    app.key(KeyEvent::from(KeyCode::Up));
+    app.key(KeyEvent::from(KeyCode::PageUp));
+    assert_eq!(app.multiplier, 12);
+    app.key(KeyEvent::from(KeyCode::PageDown));
+    app.key(KeyEvent::from(KeyCode::PageDown));
+    assert_eq!(app.multiplier, 1);
+
+    app.key(KeyEvent::from(KeyCode::Enter));
+    assert_eq!(app.mode, Mode::Draw);
+    assert_eq!(app.entered.len(), 4);
+    type_keys(&mut app, "jd");
+    assert_eq!(app.mode, Mode::Deal);
+    assert_eq!(app.session.hands, 1);
+    assert_eq!(app.session.actual, 10);
+    assert!(app.message.starts_with("flush"));
+
+    app.key(KeyEvent::from(KeyCode::Esc));
+    assert!(app.quit);
+}
+
+#[test]
+fn test_draw() {
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    let mut app = App::new(3);
+    type_keys(&mut app, "th jh qh kh 2c");
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+    terminal.draw(|frame| draw(frame, &app)).unwrap();
+
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+    assert!(screen.contains("×3"));
+    assert!(screen.contains("HELD"));
+    assert!(screen.contains("4 to a royal"));
+    assert!(screen.contains("to win"));
+}
@@ -0,0 +1,100 @@
+use crate::check;
+use crate::parse_input;
+use crate::server;
+use crate::server::Hand;
+use crate::tree_check;
+
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+// The solver for a web page. Each call returns the same JSON the HTTP API
+// would, as a string for JSON.parse, and throws on bad input:
+//
+//   import init, { rank } from "./check.js";
+//   await init();
+//   JSON.parse(rank("KH 8D 3D 4D 6D", 1, 3)).holds[0].hold  // [2, 3, 4, 5]
+
+fn hand(text: &str, multiplier: i32) -> Hand {
+    Hand {
+        hand: text.to_string(),
+        multiplier,
+        dead: Vec::new(),
+        top: None,
+        hold: None,
+        odds: false,
+    }
+}
+
+fn error((_, message): (u16, String)) -> JsError {
+    JsError::new(&message)
+}
+
+// The cards in `text`, and whether they make a whole hand.
+#[wasm_bindgen]
+pub fn parse(text: &str) -> Result<String, JsError> {
+    let cards = parse_input::parse_cards(text).map_err(|()| JsError::new("Not cards"))?;
+    let cards: Vec<String> = cards.iter().map(|card| card.to_string()).collect();
+    Ok(json!({
+        "cards": cards,
+        "complete": parse_input::parse(text).is_ok(),
+    })
+    .to_string())
+}
+
+// What a hand pays as dealt, or null.
+#[wasm_bindgen]
+pub fn score(text: &str) -> Result<String, JsError> {
+    let full_hand = parse_input::parse(text).map_err(|()| JsError::new("Not a hand"))?;
+    let score = check::check(&full_hand.cards());
+    Ok(json!(score.map(|score| score.to_string())).to_string())
+}
+
+// The `top` best holds, best first.
+#[wasm_bindgen]
+pub fn rank(text: &str, multiplier: i32, top: usize) -> Result<String, JsError> {
+    let request = hand(text, multiplier);
+    let (full_hand, deck) = server::deal(&request).map_err(error)?;
+    let holds: Vec<_> = tree_check::ranked_swap_values(&full_hand, &deck, multiplier)
+        .iter()
+        .take(top)
+        .map(|(keep, value)| server::hold_json(&full_hand, full_hand.mask(keep), value, multiplier))
+        .collect();
+    Ok(json!({
+        "hand": server::text(&full_hand.cards()),
+        "multiplier": multiplier,
+        "holds": holds,
+    })
+    .to_string())
+}
+
+// The best hold with the chance of each category after the draw.
+#[wasm_bindgen]
+pub fn analyze(text: &str, multiplier: i32) -> Result<String, JsError> {
+    let request = hand(text, multiplier);
+    let (full_hand, deck) = server::deal(&request).map_err(error)?;
+    let (keep, value) = tree_check::ranked_swap_values(&full_hand, &deck, multiplier).swap_remove(0);
+    let mut best = server::hold_json(&full_hand, full_hand.mask(&keep), &value, multiplier);
+    best["odds"] = server::odds(&tree_check::check(&keep, &deck));
+    Ok(json!({
+        "hand": server::text(&full_hand.cards()),
+        "multiplier": multiplier,
+        "best": best,
+    })
+    .to_string())
+}
+
+// Only the happy paths: JsError needs a real JS host.
+#[test]
+fn test_wasm() {
+    let ranked: serde_json::Value = serde_json::from_str(&rank("KH 8D 3D 4D 6D", 1, 3).unwrap()).unwrap();
+    assert_eq!(ranked["holds"].as_array().unwrap().len(), 3);
+    assert_eq!(ranked["holds"][0]["hold"], json!([2, 3, 4, 5]));
+
+    let analysis: serde_json::Value = serde_json::from_str(&analyze("KH 8D 3D 4D 6D", 1).unwrap()).unwrap();
+    assert_eq!(analysis["best"]["hold"], ranked["holds"][0]["hold"]);
+    assert!(analysis["best"]["odds"]["flush"].as_f64().unwrap() > 0.0);
+
+    assert_eq!(score("KH KD 3D 4D 6D").unwrap(), r#""pair""#);
+    assert_eq!(score("KH 8D 3D 4D 6D").unwrap(), "null");
+    assert_eq!(parse("ah 10c").unwrap(), r#"{"cards":["AH","10C"],"complete":false}"#);
+}
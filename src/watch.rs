@@ -0,0 +1,233 @@
+use crate::server::Api;
+
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+use serde_json::json;
+
+// `check watch`: follows a file or FIFO that something else (an OCR
+// script, a stream overlay) writes hands to, a line at a time, and answers
+// each one as it lands. A line is a hand as the CLI takes it, or an
+// /analyze request as JSON when it needs a multiplier or dead cards of its
+// own. Lines that make no sense are reported and skipped.
+
+// How often to look for more of a plain file.
+const POLL: Duration = Duration::from_millis(10);
+
+// How many holds each answer lists.
+const TOP: usize = 3;
+
+// The lines added to `path` from now on, as `tail -f` would see them. A
+// file that shrinks is read again from the top; a FIFO is reopened for the
+// next writer when the last one closes it. Never ends by itself.
+pub struct Follow {
+    path: PathBuf,
+    reader: BufReader<File>,
+    fifo: bool,
+    line: Vec<u8>,
+}
+
+impl Follow {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let fifo = is_fifo(path)?;
+        let mut file = File::open(path)?;
+        if !fifo {
+            file.seek(SeekFrom::End(0))?;
+        }
+        Ok(Follow {
+            path: path.to_path_buf(),
+            reader: BufReader::new(file),
+            fifo,
+            line: Vec::new(),
+        })
+    }
+
+    // Nothing more yet: wait for a writer, or for the file to grow.
+    fn wait(&mut self) -> io::Result<()> {
+        if self.fifo {
+            // Blocks until someone opens it to write.
+            self.reader = BufReader::new(File::open(&self.path)?);
+            return Ok(());
+        }
+        thread::sleep(POLL);
+        let read = self.reader.stream_position()?;
+        if self.reader.get_ref().metadata()?.len() < read {
+            self.reader.seek(SeekFrom::Start(0))?;
+            self.line.clear();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::FileTypeExt;
+    Ok(std::fs::metadata(path)?.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+impl Iterator for Follow {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // A line still being written stays in `line` until its newline.
+            if let Err(error) = self.reader.read_until(b'\n', &mut self.line) {
+                return Some(Err(error));
+            }
+            if self.line.ends_with(b"\n") {
+                let line = String::from_utf8_lossy(&self.line).trim().to_string();
+                self.line.clear();
+                return Some(Ok(line));
+            }
+            if let Err(error) = self.wait() {
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+// The /analyze answer for one line, at `multiplier` unless it says
+// otherwise.
+pub fn answer(api: &Api, line: &str, multiplier: i32) -> Result<Value, String> {
+    let mut request = if line.starts_with('{') {
+        serde_json::from_str(line).map_err(|error| error.to_string())?
+    } else {
+        json!({ "hand": line })
+    };
+    let Some(fields) = request.as_object_mut() else {
+        return Err("Not a request".to_string());
+    };
+    fields.entry("multiplier").or_insert(multiplier.into());
+    fields.entry("top").or_insert(TOP.into());
+
+    match api.handle("POST", "/analyze", &request.to_string()) {
+        (200, analysis) => Ok(analysis),
+        (_, error) => Err(error["error"].as_str().unwrap_or_default().to_string()),
+    }
+}
+
+// One line of text for an answer, e.g.
+// "KH 8D 3D 4D 6D x1: hold 8D 3D 4D 6D (2 3 4 5), 4 to a flush, 2.43 a chip"
+pub fn summary(analysis: &Value) -> String {
+    let best = &analysis["holds"][0];
+    let positions: Vec<String> = best["hold"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|position| position.to_string())
+        .collect();
+    let cards = best["cards"].as_str().filter(|cards| !cards.is_empty()).unwrap_or("nothing");
+    format!(
+        "{} x{}: hold {cards} ({}), {}, {:.2} a chip",
+        analysis["hand"].as_str().unwrap_or_default(),
+        analysis["multiplier"],
+        positions.join(" "),
+        best["class"].as_str().unwrap_or_default(),
+        best["expected"].as_f64().unwrap_or_default(),
+    )
+}
+
+// Answers each of `lines` on `output`, as JSON or a line of text. Bad lines
+// get an error in their place, to `output` as JSON so a reader can pair
+// them up, otherwise to stderr.
+pub fn run(
+    lines: impl Iterator<Item = io::Result<String>>,
+    multiplier: i32,
+    as_json: bool,
+    mut output: impl Write,
+) -> io::Result<()> {
+    let api = Api::default();
+    for line in lines {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        match (answer(&api, &line, multiplier), as_json) {
+            (Ok(analysis), true) => writeln!(output, "{analysis}")?,
+            (Ok(analysis), false) => writeln!(output, "{}", summary(&analysis))?,
+            (Err(error), true) => writeln!(output, "{}", json!({ "line": line, "error": error }))?,
+            (Err(error), false) => eprintln!("Skipped {line:?}: {error}"),
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_answer() {
+    let api = Api::default();
+    let analysis = answer(&api, "kh 8d 3d 4d 6d", 1).unwrap();
+    assert_eq!(analysis["holds"].as_array().unwrap().len(), TOP);
+    assert_eq!(
+        summary(&analysis),
+        "KH 8D 3D 4D 6D x1: hold 8D 3D 4D 6D (2 3 4 5), 4 to a flush, 2.43 a chip"
+    );
+
+    let analysis = answer(&api, r#"{"hand": "KH 8D 3D 4D 6D", "multiplier": 10, "top": 1}"#, 1).unwrap();
+    assert_eq!(analysis["multiplier"], 10);
+    assert_eq!(analysis["holds"].as_array().unwrap().len(), 1);
+
+    assert!(answer(&api, "KH KH", 1).is_err());
+    assert!(answer(&api, "{not json", 1).is_err());
+    assert!(answer(&api, "[1, 2]", 1).is_err());
+    assert!(answer(&api, "KH 8D 3D 4D 6D", 0).is_err());
+}
+
+#[test]
+fn test_run() {
+    let lines = ["KH 8D 3D 4D 6D", "", "garbage", "AH AD AC AS KH"].map(|line| Ok(line.to_string()));
+    let mut output = Vec::new();
+    run(lines.into_iter(), 1, true, &mut output).unwrap();
+    let answers: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(answers.len(), 3);
+    assert_eq!(answers[0]["holds"][0]["hold"], json!([2, 3, 4, 5]));
+    assert_eq!(answers[1]["line"], "garbage");
+    assert_eq!(answers[2]["holds"][0]["class"], "four of a kind");
+}
+
+#[test]
+fn test_follow() {
+    let path = std::env::temp_dir().join(format!("check-watch-{}.txt", std::process::id()));
+    std::fs::write(&path, "AH AD AC AS KH\n").unwrap();
+    let mut follow = Follow::open(&path).unwrap();
+
+    let writer = {
+        let path = path.clone();
+        thread::spawn(move || {
+            let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+            // Half a line, then the rest, then one more.
+            file.write_all(b"KH 8D ").unwrap();
+            file.flush().unwrap();
+            thread::sleep(POLL * 3);
+            file.write_all(b"3D 4D 6D\n2C 3C 4C 5C 6C\n").unwrap();
+        })
+    };
+    // Only what's written after opening, and only whole lines.
+    assert_eq!(follow.next().unwrap().unwrap(), "KH 8D 3D 4D 6D");
+    assert_eq!(follow.next().unwrap().unwrap(), "2C 3C 4C 5C 6C");
+    writer.join().unwrap();
+
+    // Truncated, as when a log is rotated.
+    std::fs::write(&path, "9S 9H 2D 4C 7S\n").unwrap();
+    assert_eq!(follow.next().unwrap().unwrap(), "9S 9H 2D 4C 7S");
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,47 @@
+/* Drives the C API the way an embedding program would. Built and run by
+ * tests/ffi.rs; exits non-zero on the first thing that's wrong. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "check.h"
+
+#define EXPECT(condition)                                                    \
+    do {                                                                     \
+        if (!(condition)) {                                                  \
+            fprintf(stderr, "%s:%d: failed: %s\n", __FILE__, __LINE__,       \
+                    #condition);                                             \
+            return 1;                                                        \
+        }                                                                    \
+    } while (0)
+
+int main(void) {
+    CheckHand *hand = NULL;
+    EXPECT(check_parse("KH 8D 3D 4D 6D", &hand) == CHECK_STATUS_OK);
+
+    CheckHolds holds = {0};
+    EXPECT(check_analyze(hand, 1, 3, &holds) == CHECK_STATUS_OK);
+    EXPECT(holds.len == 3);
+    /* Keep the four diamonds, slots 2-5. */
+    EXPECT(holds.holds[0].mask == 0x1e);
+    EXPECT(holds.holds[0].numerator == 114 && holds.holds[0].denominator == 47);
+    for (size_t i = 1; i < holds.len; i++) {
+        const CheckHold *better = &holds.holds[i - 1];
+        const CheckHold *worse = &holds.holds[i];
+        EXPECT(better->numerator * worse->denominator >= worse->numerator * better->denominator);
+    }
+    check_holds_free(&holds);
+    EXPECT(holds.holds == NULL && holds.len == 0);
+
+    EXPECT(check_analyze(hand, 10000, 3, &holds) == CHECK_STATUS_BAD_MULTIPLIER);
+    EXPECT(strcmp(check_status_message(CHECK_STATUS_BAD_MULTIPLIER), "multiplier isn't 1-9999") == 0);
+    check_hand_free(hand);
+
+    CheckHand *bad = NULL;
+    EXPECT(check_parse("KH KH 3D 4D 6D", &bad) == CHECK_STATUS_BAD_HAND);
+    EXPECT(bad == NULL);
+    EXPECT(check_parse(NULL, &bad) == CHECK_STATUS_NULL_POINTER);
+
+    printf("ok\n");
+    return 0;
+}
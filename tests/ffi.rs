@@ -0,0 +1,39 @@
+// Builds tests/c/check_test.c against the cdylib and runs it, so the C API
+// is checked from C and against the committed header.
+
+// As a Python extension the library leaves libpython's symbols for the
+// interpreter to fill in, so a plain C program can't link it.
+#![cfg(not(feature = "python"))]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn test_c_program() {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // target/<profile>/deps/ffi-... -> target/<profile>/deps, where cargo
+    // test builds libcheck. The copy in target/<profile> is only refreshed
+    // by cargo build, so it can be stale.
+    let deps = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let program = deps.join("check_test");
+
+    let compiled = Command::new(std::env::var("CC").unwrap_or("cc".to_string()))
+        .arg(manifest.join("tests/c/check_test.c"))
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg("-L")
+        .arg(&deps)
+        .args(["-lcheck", "-Wall", "-Werror", "-o"])
+        .arg(&program)
+        .status()
+        .expect("No C compiler");
+    assert!(compiled.success());
+
+    let output = Command::new(&program)
+        .env("LD_LIBRARY_PATH", &deps)
+        .env("DYLD_LIBRARY_PATH", &deps)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,29 @@
+// Loads the extension module into python3 and runs tests/python/test_check.py
+// against it, the way a notebook would import it.
+
+#![cfg(feature = "python")]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn test_python_module() {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // target/<profile>/deps/python-... -> target/<profile>/deps, where
+    // cargo test builds libcheck. The copy in target/<profile> is only
+    // refreshed by cargo build, so it can be stale.
+    let deps = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    // Python only imports the module by its own name.
+    let module = deps.parent().unwrap().join("python");
+    std::fs::create_dir_all(&module).unwrap();
+    let library = format!("{}check{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX);
+    std::fs::copy(deps.join(library), module.join("check.so")).unwrap();
+
+    let output = Command::new(std::env::var("PYTHON").unwrap_or("python3".to_string()))
+        .arg(manifest.join("tests/python/test_check.py"))
+        .env("PYTHONPATH", &module)
+        .output()
+        .expect("No python3");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,55 @@
+# Run by tests/python.rs with the freshly built extension on sys.path.
+
+import threading
+from fractions import Fraction
+
+import check
+
+hand = check.parse("KH 8D 3D 4D 6D")
+assert repr(hand) == "FullHand('KH 8D 3D 4D 6D')"
+assert check.FullHand("kh 8d 3d 4d 6d").cards == hand.cards
+assert hand.keep(0b11110) == hand.cards[1:]
+
+ranked = check.ranked_swap_values(hand)
+assert len(ranked) == 32
+assert ranked[0] == (hand.cards[1:], Fraction(114, 47))
+assert all(a[1] >= b[1] for a, b in zip(ranked, ranked[1:]))
+
+# Without the 5D there is no straight flush draw, so a high stake pays less.
+live = check.ranked_swap_values(hand, 100)[0][1]
+dead = check.ranked_swap_values(hand, 100, dead=[check.Card("5D")])[0][1]
+assert dead < live
+
+odds = check.odds(hand, 0b11110)
+assert odds == {"flush": Fraction(9, 47), "pair": Fraction(12, 47), "nothing": Fraction(26, 47)}
+assert sum(odds.values()) == 1
+
+card = check.Card("10d")
+assert (card.rank, card.suit, str(card)) == (10, "D", "10D")
+assert check.Card("ah") == check.Card("AH")
+
+for bad in [
+    lambda: check.parse("KH KH"),
+    lambda: check.Card("KH 8D"),
+    lambda: check.ranked_swap_values(hand, 0),
+    lambda: check.odds(hand, 32),
+]:
+    try:
+        bad()
+    except ValueError:
+        pass
+    else:
+        raise AssertionError("no ValueError")
+
+# The solver lets go of the GIL, so threads can share it.
+results = [None] * 4
+def solve(i):
+    results[i] = check.ranked_swap_values(hand)[0]
+threads = [threading.Thread(target=solve, args=(i,)) for i in range(len(results))]
+for thread in threads:
+    thread.start()
+for thread in threads:
+    thread.join()
+assert results == [ranked[0]] * len(results)
+
+print("ok")